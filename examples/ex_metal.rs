@@ -63,5 +63,8 @@ fn main() {
         dist_to_focus,
     );
 
-    ray_tracer::ray_tracer(&world, &cam, nx, ny, "ex_metal.jpg");
+    if let Err(e) = ray_tracer::ray_tracer(&world, &cam, nx, ny, "ex_metal.jpg") {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
 }
@@ -4,9 +4,9 @@ use ray_tracer::camera::Camera;
 use ray_tracer::materials::{
     dielectric::Dielectric, lambertian::Lambertian, metal::Metal, Material,
 };
+use ray_tracer::reflexible::bvh::Bvh;
 use ray_tracer::reflexible::sphere::Sphere;
 use ray_tracer::reflexible::Reflexible;
-use ray_tracer::reflexible::ReflexibleList;
 use ray_tracer::vec3::Vec3;
 
 use rand::Rng;
@@ -85,7 +85,7 @@ fn main() {
         Material::Dielectric(Dielectric::new(1.5)),
     )));
 
-    let world = ReflexibleList::new(list);
+    let world = Bvh::new(list);
 
     let lookfrom = Vec3::new(0.0, 0.5, 7.0);
     let lookat = Vec3::new(0.0, 0.0, -1.0);
@@ -102,5 +102,8 @@ fn main() {
         dist_to_focus,
     );
 
-    ray_tracer::ray_tracer(&world, &cam, nx, ny, "ex_random.jpg");
+    if let Err(e) = ray_tracer::ray_tracer(&world, &cam, nx, ny, "ex_random.jpg") {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
 }
@@ -0,0 +1,183 @@
+use crate::adaptive::PixelStats;
+use crate::aov::{self, Aov, AovSet, Aovs};
+use crate::framebuffer::Framebuffer;
+use crate::tile::Tile;
+use crate::vec3::Vec3;
+
+pub const AOV_COUNT: usize = aov::NAMES.len();
+
+// everything a pixel has accumulated. pixels keep track of their own sample count, so a
+// render can stop at any point and be continued later.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PixelSamples {
+    pub sum: Vec3,
+    pub stats: PixelStats,
+    // indexed by `Aov as usize`, ids are only set by the first sample
+    pub aov_sums: [Vec3; AOV_COUNT],
+}
+
+impl PixelSamples {
+    pub fn new() -> PixelSamples {
+        PixelSamples {
+            sum: Vec3::new(0.0, 0.0, 0.0),
+            stats: PixelStats::new(),
+            aov_sums: [Vec3::new(0.0, 0.0, 0.0); AOV_COUNT],
+        }
+    }
+}
+
+impl Default for PixelSamples {
+    fn default() -> PixelSamples {
+        PixelSamples::new()
+    }
+}
+
+// the running sums of a render, resolved into images on demand. aov sums are only stored
+// when asked for, most renders don't need them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Accumulator {
+    width: u32,
+    height: u32,
+    aovs: AovSet,
+    sums: Vec<Vec3>,
+    stats: Vec<PixelStats>,
+    aov_sums: Vec<[Vec3; AOV_COUNT]>,
+}
+
+impl Accumulator {
+    pub fn new(width: u32, height: u32, aovs: AovSet) -> Accumulator {
+        let size = width as usize * height as usize;
+
+        Accumulator {
+            width,
+            height,
+            aovs,
+            sums: vec![Vec3::new(0.0, 0.0, 0.0); size],
+            stats: vec![PixelStats::new(); size],
+            aov_sums: if aovs.is_empty() {
+                Vec::new()
+            } else {
+                vec![[Vec3::new(0.0, 0.0, 0.0); AOV_COUNT]; size]
+            },
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn aovs(&self) -> AovSet {
+        self.aovs
+    }
+
+    // the fewest samples any pixel has
+    pub fn min_samples(&self) -> u32 {
+        self.stats.iter().map(|s| s.count()).min().unwrap_or(0)
+    }
+
+    pub fn total_samples(&self) -> u64 {
+        self.stats.iter().map(|s| u64::from(s.count())).sum()
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> PixelSamples {
+        let index = self.index(x, y);
+
+        PixelSamples {
+            sum: self.sums[index],
+            stats: self.stats[index],
+            aov_sums: self
+                .aov_sums
+                .get(index)
+                .cloned()
+                .unwrap_or([Vec3::new(0.0, 0.0, 0.0); AOV_COUNT]),
+        }
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, pixel: &PixelSamples) {
+        let index = self.index(x, y);
+
+        self.sums[index] = pixel.sum;
+        self.stats[index] = pixel.stats;
+        if let Some(aov_sums) = self.aov_sums.get_mut(index) {
+            *aov_sums = pixel.aov_sums;
+        }
+    }
+
+    // the tile's pixels, row by row
+    pub fn read_tile(&self, tile: &Tile, pixels: &mut Vec<PixelSamples>) {
+        pixels.clear();
+        for y in tile.y0..tile.y1 {
+            for x in tile.x0..tile.x1 {
+                pixels.push(self.get(x, y));
+            }
+        }
+    }
+
+    pub fn write_tile(&mut self, tile: &Tile, pixels: &[PixelSamples]) {
+        let mut pixels = pixels.iter();
+        for y in tile.y0..tile.y1 {
+            for x in tile.x0..tile.x1 {
+                self.set(x, y, pixels.next().unwrap());
+            }
+        }
+    }
+
+    // the average of every pixel's samples, black for pixels without any
+    pub fn resolve(&self) -> (Framebuffer, Aovs) {
+        let mut fb = Framebuffer::new(self.width, self.height);
+        let mut aovs = Aovs::new(self.aovs, self.width, self.height);
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let index = self.index(x, y);
+                let n = f64::from(self.stats[index].count());
+                if n == 0.0 {
+                    continue;
+                }
+
+                fb.set(x, y, self.sums[index] / n);
+                for aov in self.aovs.iter() {
+                    let sum = self.aov_sums[index][aov as usize];
+                    let value = match aov {
+                        Aov::SampleCount => Vec3::new(n, n, n),
+                        _ if aov.is_id() => sum,
+                        _ => sum / n,
+                    };
+                    aovs.get_mut(aov).unwrap().set(x, y, value);
+                }
+            }
+        }
+
+        (fb, aovs)
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        y as usize * self.width as usize + x as usize
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn resolve_averages() {
+        let mut acc = Accumulator::new(2, 1, AovSet::new().with(Aov::SampleCount));
+        let mut pixel = acc.get(1, 0);
+        for v in &[1.0, 3.0] {
+            pixel.sum = pixel.sum + Vec3::new(*v, 0.0, 0.0);
+            pixel.stats.add(Vec3::new(*v, 0.0, 0.0));
+        }
+        acc.set(1, 0, &pixel);
+
+        let (fb, aovs) = acc.resolve();
+        assert_eq!(fb.get(0, 0), Vec3::new(0.0, 0.0, 0.0));
+        assert_eq!(fb.get(1, 0), Vec3::new(2.0, 0.0, 0.0));
+        assert_eq!(aovs.get(Aov::SampleCount).unwrap().get(1, 0).x, 2.0);
+        assert_eq!((acc.min_samples(), acc.total_samples()), (0, 2));
+    }
+}
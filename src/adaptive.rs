@@ -0,0 +1,116 @@
+use crate::vec3::Vec3;
+
+// samples taken between two convergence checks of a pixel
+pub const CHECK_INTERVAL: u32 = 8;
+
+// relative errors are measured against at least this luminance, so that nearly black
+// pixels don't need huge sample counts to look smooth
+const MIN_LUMINANCE: f64 = 0.05;
+
+// stops sampling a pixel once the standard error of its mean luminance drops below
+// `threshold` relative to the mean. `RenderSettings::samples` stays the maximum.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AdaptiveSampling {
+    pub threshold: f64,
+    // taken before the first check, too few samples can miss a small bright feature
+    pub min_samples: u32,
+}
+
+impl AdaptiveSampling {
+    pub fn new(threshold: f64) -> AdaptiveSampling {
+        AdaptiveSampling {
+            threshold,
+            min_samples: 16,
+        }
+    }
+
+    // whether to stop after the samples in `stats`
+    pub fn converged(&self, stats: &PixelStats) -> bool {
+        let n = stats.count();
+        if n < self.min_samples || !(n - self.min_samples).is_multiple_of(CHECK_INTERVAL) {
+            return false;
+        }
+        stats.relative_error() <= self.threshold
+    }
+}
+
+// running luminance statistics of one pixel's samples
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PixelStats {
+    count: u32,
+    sum: f64,
+    sum_sq: f64,
+}
+
+impl PixelStats {
+    pub fn new() -> PixelStats {
+        PixelStats::default()
+    }
+
+    pub fn add(&mut self, col: Vec3) {
+        let y = luminance(col);
+        self.count += 1;
+        self.sum += y;
+        self.sum_sq += y * y;
+    }
+
+    // rebuilds statistics saved from `parts`
+    pub fn from_parts(count: u32, sum: f64, sum_sq: f64) -> PixelStats {
+        PixelStats { count, sum, sum_sq }
+    }
+
+    pub fn parts(&self) -> (u32, f64, f64) {
+        (self.count, self.sum, self.sum_sq)
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    // standard error of the mean over the mean
+    pub fn relative_error(&self) -> f64 {
+        if self.count < 2 {
+            return f64::INFINITY;
+        }
+
+        let n = f64::from(self.count);
+        let mean = self.sum / n;
+        let variance = ((self.sum_sq - n * mean * mean) / (n - 1.0)).max(0.0);
+        (variance / n).sqrt() / mean.max(MIN_LUMINANCE)
+    }
+}
+
+pub fn luminance(col: Vec3) -> f64 {
+    0.2126 * col.r() + 0.7152 * col.g() + 0.0722 * col.b()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn flat_pixels_converge_at_the_minimum() {
+        let adaptive = AdaptiveSampling::new(0.01);
+        let mut stats = PixelStats::new();
+
+        for _ in 0..15 {
+            stats.add(Vec3::new(0.5, 0.7, 1.0));
+            assert!(!adaptive.converged(&stats));
+        }
+        stats.add(Vec3::new(0.5, 0.7, 1.0));
+        assert!(adaptive.converged(&stats));
+    }
+
+    #[test]
+    fn noisy_pixels_keep_sampling() {
+        let adaptive = AdaptiveSampling::new(0.01);
+        let mut stats = PixelStats::new();
+
+        for i in 0..64 {
+            let v = if i % 2 == 0 { 0.0 } else { 1.0 };
+            stats.add(Vec3::new(v, v, v));
+            assert!(!adaptive.converged(&stats));
+        }
+        assert!((stats.relative_error() - 0.125).abs() < 0.01);
+    }
+}
@@ -0,0 +1,339 @@
+use crate::framebuffer::Framebuffer;
+use crate::materials::{Material, Scatterable};
+use crate::ray::Ray;
+use crate::reflexible::{HitRecord, Reflexible};
+use crate::vec3::Vec3;
+
+use std::collections::HashMap;
+
+pub const NAMES: [&str; 7] = [
+    "depth",
+    "normal",
+    "albedo",
+    "position",
+    "object_id",
+    "material_id",
+    "sample_count",
+];
+
+// auxiliary buffers taken from the first hit of each camera ray. pixels whose rays miss
+// everything are zero.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Aov {
+    // distance from the camera
+    Depth,
+    // world space
+    Normal,
+    Albedo,
+    // world space
+    Position,
+    // 1-based index in the world's object list, see `HitRecord::object`
+    ObjectId,
+    // 1-based index in the world's materials, see `MaterialIds`
+    MaterialId,
+    // samples taken per pixel, which varies with adaptive sampling
+    SampleCount,
+}
+
+impl Aov {
+    pub const ALL: [Aov; 7] = [
+        Aov::Depth,
+        Aov::Normal,
+        Aov::Albedo,
+        Aov::Position,
+        Aov::ObjectId,
+        Aov::MaterialId,
+        Aov::SampleCount,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Aov> {
+        Aov::ALL.iter().cloned().find(|aov| aov.name() == name)
+    }
+
+    pub fn name(&self) -> &'static str {
+        NAMES[*self as usize]
+    }
+
+    // channel names of the aov's layer in exr files
+    pub fn channels(&self) -> &'static [&'static str] {
+        match *self {
+            Aov::Depth => &["Z"],
+            Aov::Normal | Aov::Position => &["X", "Y", "Z"],
+            Aov::Albedo => &["R", "G", "B"],
+            Aov::ObjectId | Aov::MaterialId => &["id"],
+            Aov::SampleCount => &["count"],
+        }
+    }
+
+    // ids are taken from the first sample of a pixel, averaging them would be meaningless
+    pub fn is_id(&self) -> bool {
+        match *self {
+            Aov::ObjectId | Aov::MaterialId => true,
+            Aov::Depth | Aov::Normal | Aov::Albedo | Aov::Position | Aov::SampleCount => false,
+        }
+    }
+
+    // single channel aovs are stored in every channel of the framebuffer.
+    // sample counts aren't known from a single hit and are zero here.
+    pub fn value(&self, r: &Ray, rec: &HitRecord, materials: &MaterialIds) -> Vec3 {
+        match *self {
+            Aov::Depth => splat(rec.t * r.direction.length()),
+            Aov::Normal => Vec3::unit_vector(rec.normal),
+            Aov::Albedo => rec.material.albedo(rec),
+            Aov::Position => rec.p,
+            Aov::ObjectId => splat(rec.object as f64),
+            Aov::MaterialId => splat(materials.get(rec.material) as f64),
+            Aov::SampleCount => splat(0.0),
+        }
+    }
+
+    // maps the aov into [0, 1] for viewing it as an 8-bit image
+    pub fn to_display(&self, fb: &Framebuffer) -> Framebuffer {
+        let mut display = Framebuffer::new(fb.width(), fb.height());
+
+        // depth and sample count
+        let max_x = fb.pixels().iter().fold(0.0, |acc: f64, p| acc.max(p.x));
+        let (min, max) = fb.pixels().iter().fold(
+            (
+                Vec3::new(f64::MAX, f64::MAX, f64::MAX),
+                Vec3::new(f64::MIN, f64::MIN, f64::MIN),
+            ),
+            |(min, max), p| {
+                (
+                    Vec3::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z)),
+                    Vec3::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z)),
+                )
+            },
+        );
+
+        for y in 0..fb.height() {
+            for x in 0..fb.width() {
+                let p = fb.get(x, y);
+                let col = match *self {
+                    Aov::Depth if max_x > 0.0 => p / max_x,
+                    Aov::Depth => p,
+                    Aov::Normal => 0.5 * (p + Vec3::new(1.0, 1.0, 1.0)),
+                    Aov::Albedo => p,
+                    Aov::Position => Vec3::new(
+                        normalize(p.x, min.x, max.x),
+                        normalize(p.y, min.y, max.y),
+                        normalize(p.z, min.z, max.z),
+                    ),
+                    Aov::ObjectId | Aov::MaterialId => id_color(p.x as u64),
+                    Aov::SampleCount if max_x > 0.0 => heat(p.x / max_x),
+                    Aov::SampleCount => heat(0.0),
+                };
+                display.set(x, y, col);
+            }
+        }
+
+        display
+    }
+}
+
+fn splat(v: f64) -> Vec3 {
+    Vec3::new(v, v, v)
+}
+
+fn normalize(v: f64, min: f64, max: f64) -> f64 {
+    if max > min {
+        (v - min) / (max - min)
+    } else {
+        0.0
+    }
+}
+
+// blue through green to red as `t` goes from 0 to 1
+fn heat(t: f64) -> Vec3 {
+    if t < 0.5 {
+        Vec3::new(0.0, 2.0 * t, 1.0 - 2.0 * t)
+    } else {
+        Vec3::new(2.0 * t - 1.0, 2.0 - 2.0 * t, 0.0)
+    }
+}
+
+// an arbitrary but stable color per id, black for nothing
+fn id_color(id: u64) -> Vec3 {
+    if id == 0 {
+        return splat(0.0);
+    }
+
+    let mut h = id.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    h ^= h >> 29;
+    let channel = |shift: u32| 0.2 + 0.8 * ((h >> shift) & 0xff) as f64 / 255.0;
+    Vec3::new(channel(0), channel(8), channel(16))
+}
+
+// numbers the world's materials from 1 in the order `Reflexible::materials` lists them,
+// so ids don't depend on the camera. objects sharing a material share its id, materials
+// are told apart by address like in `LightList::find`.
+pub struct MaterialIds {
+    // by address
+    ids: HashMap<usize, usize>,
+}
+
+impl MaterialIds {
+    pub fn new(world: &dyn Reflexible) -> MaterialIds {
+        let mut materials = Vec::new();
+        world.materials(&mut materials);
+
+        let mut ids = HashMap::new();
+        for material in materials {
+            let next = ids.len() + 1;
+            ids.entry(address(material)).or_insert(next);
+        }
+        MaterialIds { ids }
+    }
+
+    // 0 for materials the world doesn't list
+    pub fn get(&self, material: &Material) -> usize {
+        self.ids.get(&address(material)).cloned().unwrap_or(0)
+    }
+}
+
+fn address(material: &Material) -> usize {
+    material as *const Material as usize
+}
+
+// the set of aovs to render. a bit set, so that it can live in the `Copy` render settings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AovSet(u8);
+
+impl AovSet {
+    pub fn new() -> AovSet {
+        AovSet(0)
+    }
+
+    pub fn with(mut self, aov: Aov) -> AovSet {
+        self.insert(aov);
+        self
+    }
+
+    pub fn insert(&mut self, aov: Aov) {
+        self.0 |= 1 << aov as u8;
+    }
+
+    pub fn contains(&self, aov: Aov) -> bool {
+        self.0 & (1 << aov as u8) != 0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn bits(&self) -> u8 {
+        self.0
+    }
+
+    // None if `bits` has any bit that isn't an aov
+    pub fn from_bits(bits: u8) -> Option<AovSet> {
+        if u32::from(bits) >> Aov::ALL.len() == 0 {
+            Some(AovSet(bits))
+        } else {
+            None
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Aov> {
+        let set = *self;
+        Aov::ALL
+            .iter()
+            .cloned()
+            .filter(move |aov| set.contains(*aov))
+    }
+}
+
+// the rendered aov buffers, in the order of `Aov::ALL`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Aovs {
+    buffers: Vec<(Aov, Framebuffer)>,
+}
+
+impl Aovs {
+    pub fn new(set: AovSet, width: u32, height: u32) -> Aovs {
+        Aovs {
+            buffers: set
+                .iter()
+                .map(|aov| (aov, Framebuffer::new(width, height)))
+                .collect(),
+        }
+    }
+
+    pub fn get(&self, aov: Aov) -> Option<&Framebuffer> {
+        self.buffers.iter().find(|b| b.0 == aov).map(|b| &b.1)
+    }
+
+    pub fn get_mut(&mut self, aov: Aov) -> Option<&mut Framebuffer> {
+        self.buffers
+            .iter_mut()
+            .find(|b| b.0 == aov)
+            .map(|b| &mut b.1)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Aov, &Framebuffer)> {
+        self.buffers.iter().map(|b| (b.0, &b.1))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffers.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::lambertian::Lambertian;
+    use crate::reflexible::bvh::Bvh;
+    use crate::reflexible::sphere::Sphere;
+
+    use std::sync::Arc;
+
+    #[test]
+    fn names() {
+        for (name, aov) in NAMES.iter().zip(Aov::ALL.iter()) {
+            assert_eq!(Aov::from_name(name), Some(*aov));
+            assert_eq!(aov.name(), *name);
+        }
+        assert_eq!(Aov::from_name("beauty"), None);
+    }
+
+    #[test]
+    fn set() {
+        let set = AovSet::new().with(Aov::MaterialId).with(Aov::Depth);
+
+        assert!(set.contains(Aov::Depth));
+        assert!(!set.contains(Aov::Normal));
+        assert_eq!(
+            set.iter().collect::<Vec<_>>(),
+            vec![Aov::Depth, Aov::MaterialId]
+        );
+        assert!(AovSet::new().is_empty());
+    }
+
+    #[test]
+    fn objects_sharing_a_material_share_an_id() {
+        let red = Arc::new(Material::Lambertian(Lambertian::new(Vec3::new(
+            1.0, 0.0, 0.0,
+        ))));
+        let other_red = Material::Lambertian(Lambertian::new(Vec3::new(1.0, 0.0, 0.0)));
+        let blue = Material::Lambertian(Lambertian::new(Vec3::new(0.0, 0.0, 1.0)));
+        let sphere = |x: f64, material: Arc<Material>| {
+            Box::new(Sphere::new(Vec3::new(x, 0.0, 0.0), 0.5, material)) as Box<dyn Reflexible>
+        };
+        let world = Bvh::new(vec![
+            sphere(0.0, Arc::new(blue)),
+            sphere(2.0, red.clone()),
+            sphere(4.0, Arc::new(other_red)),
+            sphere(6.0, red.clone()),
+        ]);
+
+        let ids = MaterialIds::new(&world);
+        let id = |x: f64| {
+            let r = Ray::new(Vec3::new(x, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+            ids.get(world.hit(&r, 0.001, f64::MAX).unwrap().material)
+        };
+        // numbered in the order of the list, not of the tree
+        assert_eq!((id(0.0), id(2.0), id(4.0), id(6.0)), (1, 2, 3, 2));
+        assert_eq!(ids.get(&red.as_ref().clone()), 0);
+    }
+}
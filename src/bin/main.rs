@@ -1,106 +1,655 @@
 extern crate ray_tracer;
 
-use ray_tracer::camera::Camera;
-use ray_tracer::materials::{
-    dielectric::Dielectric, lambertian::Lambertian, metal::Metal, Material,
-};
-use ray_tracer::reflexible::sphere::Sphere;
-use ray_tracer::reflexible::Reflexible;
-use ray_tracer::reflexible::ReflexibleList;
-use ray_tracer::vec3::Vec3;
+use ray_tracer::accumulator::Accumulator;
+use ray_tracer::adaptive::AdaptiveSampling;
+use ray_tracer::aov::{self, Aov, AovSet};
+use ray_tracer::checkpoint::{self, CheckpointWriter};
+use ray_tracer::error::Error;
+use ray_tracer::integrators::ambient_occlusion::AmbientOcclusion;
+use ray_tracer::integrators::{self, BuiltinIntegrator};
+use ray_tracer::output::{self, OutputFormat, SnapshotWriter};
+use ray_tracer::presets;
+use ray_tracer::progress::{CancelToken, ConsoleProgress, NoProgress, Progress, ProgressObserver};
+use ray_tracer::reflexible::bvh::Bvh;
+use ray_tracer::scene::{self, Scene};
+use ray_tracer::tonemap::{self, ToneMap, Transfer};
 
-use rand::Rng;
+use rand::SeedableRng;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const USAGE: &str = "usage: {} [options]
+
+options:
+  -s, --scene <file>      render a toml scene file
+  -p, --preset <name>     render a built-in scene: random (default), lambertian, metal, dielectric
+  -W, --width <pixels>    image width (default 1920, or the scene's)
+  -H, --height <pixels>   image height (default 1080, or the scene's)
+  -n, --samples <n>       samples per pixel, the maximum with --noise-threshold
+      --noise-threshold <t>
+                          stop sampling a pixel once its relative error is below t
+      --min-samples <n>   samples per pixel before checking the noise (default 16)
+  -d, --depth <n>         maximum bounce depth
+      --min-depth <n>     bounces before paths can be ended at random (default 5)
+      --integrator <name> path (default), ambient_occlusion, normal, albedo or uv
+      --ao-distance <d>   ambient occlusion, with rays occluded up to d away
+                          (default: the scene's, or the camera focus distance);
+                          implies --integrator ambient_occlusion and can't be used
+                          with another one
+      --progressive       render one sample per pixel at a time over the whole image
+      --snapshot-passes <n>
+                          progressive, writing the output every n passes
+      --snapshot-seconds <s>
+                          progressive, writing the output every s seconds
+      --time-limit <s>    progressive, stopping after s seconds
+      --checkpoint <file> save the samples taken so far to file every few minutes
+      --checkpoint-seconds <s>
+                          seconds between checkpoints (default 300)
+      --resume            continue the render saved in the --checkpoint file
+  -j, --threads <n>       render threads (default: all cores)
+      --seed <n>          seed for sampling and the random preset
+  -o, --output <file>     output image (default output.jpg)
+  -f, --format <format>   png, jpeg, bmp, ppm, exr, hdr or pfm
+                          (default: from the output extension)
+      --tone-map <name>   clamp (default), reinhard, reinhard_extended, aces or hable
+      --exposure <stops>  brighten or darken the image before tone mapping
+      --srgb              encode 8-bit images with the srgb curve instead of gamma 2
+      --dither            dither 8-bit images to hide banding
+      --aovs <list>       also write these comma separated passes: depth, normal, albedo,
+                          position, object_id, material_id and sample_count. exr files
+                          get them as layers, other formats as <output>.<pass>.<ext>
+  -q, --quiet             no progress output
+  -h, --help              print this help";
+
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(300);
+
+struct Options {
+    scene: Option<String>,
+    preset: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    samples: Option<u32>,
+    noise_threshold: Option<f64>,
+    min_samples: Option<u32>,
+    depth: Option<u32>,
+    min_depth: Option<u32>,
+    integrator: Option<BuiltinIntegrator>,
+    ao_distance: Option<f64>,
+    progressive: bool,
+    snapshot_passes: Option<u32>,
+    snapshot_interval: Option<Duration>,
+    time_limit: Option<Duration>,
+    checkpoint: Option<String>,
+    checkpoint_interval: Option<Duration>,
+    resume: bool,
+    threads: Option<usize>,
+    seed: Option<u64>,
+    output: String,
+    format: Option<OutputFormat>,
+    tone_map: Option<ToneMap>,
+    exposure: Option<f64>,
+    srgb: bool,
+    dither: bool,
+    aovs: AovSet,
+    quiet: bool,
+}
 
 fn main() {
-    //screen resolution
-    let nx = 1920;
-    let ny = 1080;
+    let mut args = env::args();
+    let program = args.next().unwrap_or_else(|| "ray_tracer".to_string());
+
+    let options = match parse_args(args) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE.replacen("{}", &program, 1));
+            return;
+        }
+        Err(msg) => {
+            eprintln!("error: {}", msg);
+            eprintln!("try '{} --help' for more information", program);
+            process::exit(2);
+        }
+    };
+
+    if let Err(e) = run(&options) {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let format = match options.format {
+        Some(format) => format,
+        None => OutputFormat::from_path(Path::new(&options.output)).map_err(|e| e.to_string())?,
+    };
+
+    let (mut scene, description) = load(options)?;
+    let scene_key = checkpoint::scene_key(&description, scene.camera_params());
+    let (width, height) = (
+        options.width.unwrap_or(scene.width),
+        options.height.unwrap_or(scene.height),
+    );
+    if (width, height) != (scene.width, scene.height) {
+        scene.set_size(width, height);
+    }
+
+    let mut settings = scene.settings;
+    if let Some(samples) = options.samples {
+        settings.samples = samples;
+    }
+    if let Some(threshold) = options.noise_threshold {
+        let mut adaptive = AdaptiveSampling::new(threshold);
+        if let Some(min_samples) = options.min_samples {
+            adaptive.min_samples = min_samples;
+        }
+        settings.adaptive = Some(adaptive);
+    }
+    if let Some(depth) = options.depth {
+        settings.max_depth = depth;
+    }
+    if let Some(depth) = options.min_depth {
+        settings.min_depth = depth;
+    }
+    if let Some(integrator) = options.integrator {
+        // naming the scene's own ambient occlusion keeps its distance
+        let same = matches!(
+            (integrator, settings.integrator),
+            (
+                BuiltinIntegrator::AmbientOcclusion(_),
+                BuiltinIntegrator::AmbientOcclusion(_)
+            )
+        );
+        if !same {
+            settings.integrator = integrator;
+        }
+    }
+    if let Some(distance) = options.ao_distance {
+        settings.integrator =
+            BuiltinIntegrator::AmbientOcclusion(AmbientOcclusion::new().with_distance(distance));
+    }
+    if options.progressive {
+        let mut progressive = settings.progressive.unwrap_or_default();
+        if let Some(passes) = options.snapshot_passes {
+            progressive.snapshot_passes = Some(passes);
+        }
+        if let Some(interval) = options.snapshot_interval {
+            progressive.snapshot_interval = Some(interval);
+        }
+        if let Some(limit) = options.time_limit {
+            progressive.time_budget = Some(limit);
+        }
+        settings.progressive = Some(progressive);
+    }
+    if let Some(threads) = options.threads {
+        settings.threads = threads;
+    }
+    if let Some(seed) = options.seed {
+        settings.seed = seed;
+    }
+    if let Some(tone_map) = options.tone_map {
+        settings.tone_mapping.tone_map = tone_map;
+    }
+    if let Some(exposure) = options.exposure {
+        settings.tone_mapping.exposure = exposure;
+    }
+    if options.srgb {
+        settings.tone_mapping.transfer = Transfer::Srgb;
+    }
+    if options.dither {
+        settings.tone_mapping.dither = true;
+    }
+    for aov in options.aovs.iter() {
+        settings.aovs.insert(aov);
+    }
+    if options.checkpoint.is_some() {
+        settings.checkpoint_interval =
+            Some(options.checkpoint_interval.unwrap_or(CHECKPOINT_INTERVAL));
+    }
 
-    let mut rng = rand::thread_rng();
+    let object_count = scene.world.list.len();
+    let world = Bvh::from(scene.world);
 
-    let sphere_ground = Sphere::new(
-        Vec3::new(0.0, -1000.0, 0.0),
-        1000.0,
-        Material::Lambertian(Lambertian::new(Vec3::new(0.4, 0.6, 0.4))),
+    let mut acc = match options.checkpoint {
+        Some(ref path) if options.resume => {
+            let acc = checkpoint::load(path, width, height, &settings, scene_key)
+                .map_err(|e| with_path(e, path))?;
+            if !options.quiet {
+                println!(
+                    "resuming '{}' with {} samples taken",
+                    path,
+                    acc.total_samples()
+                );
+            }
+            acc
+        }
+        _ => Accumulator::new(width, height, settings.aovs),
+    };
+
+    if !options.quiet {
+        println!(
+            "rendering {} objects at {}x{}, {}{} samples per pixel on {} threads",
+            object_count,
+            width,
+            height,
+            if settings.adaptive.is_some() {
+                "up to "
+            } else {
+                ""
+            },
+            settings.samples,
+            settings.threads
+        );
+    }
+    let console: Box<dyn ProgressObserver> = if options.quiet {
+        Box::new(NoProgress)
+    } else {
+        Box::new(ConsoleProgress::new())
+    };
+    let recorder = Recorder {
+        inner: console.as_ref(),
+        last: Mutex::new(None),
+    };
+    let checkpoints = options
+        .checkpoint
+        .as_ref()
+        .map(|path| CheckpointWriter::new(&recorder, path, &settings, scene_key));
+    let inner: &dyn ProgressObserver = match checkpoints {
+        Some(ref checkpoints) => checkpoints,
+        None => &recorder,
+    };
+    let cancel = CancelToken::new();
+    let observer = SnapshotWriter::new(
+        inner,
+        &options.output,
+        format,
+        &settings.tone_mapping,
+        &cancel,
     );
 
-    let mut list: Vec<Box<dyn Reflexible>> = vec![Box::new(sphere_ground)];
+    let now = Instant::now();
+    ray_tracer::accumulate(
+        &world,
+        &scene.camera,
+        &mut acc,
+        &settings,
+        &observer,
+        &cancel,
+    )
+    .map_err(|e| e.to_string())?;
+    observer
+        .finish()
+        .map_err(|e| with_path(e, &options.output))?;
+    if let (Some(checkpoints), Some(path)) = (checkpoints, options.checkpoint.as_ref()) {
+        checkpoints.finish().map_err(|e| with_path(e, path))?;
+    }
+    let (fb, aovs) = acc.resolve();
+    let elapsed = now.elapsed();
 
-    for a in -11..11 {
-        for b in -11..11 {
-            let choose_mat: f64 = rng.gen();
+    output::save_with_aovs(&fb, &aovs, &options.output, format, &settings.tone_mapping)
+        .map_err(|e| with_path(e, &options.output))?;
 
-            let center = Vec3::new(
-                f64::from(a) + 0.9 * rng.gen::<f64>(),
-                0.2,
-                f64::from(b) + 0.9 * rng.gen::<f64>(),
-            );
+    if !options.quiet {
+        let seconds = elapsed.as_secs_f64();
+        let samples = recorder.last.lock().unwrap().map_or(0, |p| p.samples);
+        println!(
+            "'{}' written, {:.2}s total, {:.0} samples/s",
+            options.output,
+            seconds,
+            samples as f64 / seconds
+        );
+    }
+
+    Ok(())
+}
+
+// passes progress on, keeping the last report for the summary
+struct Recorder<'a> {
+    inner: &'a dyn ProgressObserver,
+    last: Mutex<Option<Progress>>,
+}
+
+impl<'a> ProgressObserver for Recorder<'a> {
+    fn on_progress(&self, progress: &Progress) {
+        *self.last.lock().unwrap() = Some(*progress);
+        self.inner.on_progress(progress);
+    }
+
+    fn on_finish(&self, progress: &Progress, cancelled: bool) {
+        *self.last.lock().unwrap() = Some(*progress);
+        self.inner.on_finish(progress, cancelled);
+    }
+}
+
+// also returns what the scene was made from, for keying checkpoints
+fn load(options: &Options) -> Result<(Scene, Vec<u8>), String> {
+    if let Some(ref path) = options.scene {
+        let source = fs::read_to_string(path).map_err(|e| with_path(e.into(), path))?;
+        let base_dir = Path::new(path).parent().unwrap_or_else(|| Path::new(""));
+        let scene = scene::parse_scene(&source, path, base_dir).map_err(|e| with_path(e, path))?;
+        return Ok((scene, source.into_bytes()));
+    }
 
-            if (center - Vec3::new(4., 0.2, 0.)).length() > 0.9 {
-                //diffuse
-                if choose_mat < 0.5 {
-                    list.push(Box::new(Sphere::new(
-                        center,
-                        0.2,
-                        Material::Lambertian(Lambertian::new(Vec3::new(
-                            rng.gen::<f64>() * rng.gen::<f64>(),
-                            rng.gen::<f64>() * rng.gen::<f64>(),
-                            rng.gen::<f64>() * rng.gen::<f64>(),
-                        ))),
-                    )));
-                //metal
-                } else if choose_mat < 0.95 {
-                    list.push(Box::new(Sphere::new(
-                        center,
-                        0.2,
-                        Material::Metal(Metal::new(
-                            Vec3::new(
-                                0.5 * (1.0 + rng.gen::<f64>()),
-                                0.5 * (1.0 + rng.gen::<f64>()),
-                                0.5 * (1.0 + rng.gen::<f64>()),
-                            ),
-                            0.1 * rng.gen::<f64>(),
-                        )),
-                    )));
-                } else {
-                    // dielectric
-                    list.push(Box::new(Sphere::new(
-                        center,
-                        0.2,
-                        Material::Dielectric(Dielectric::new(1.5)),
-                    )));
+    let name = options.preset.as_deref().unwrap_or("random");
+    let seed = options.seed.unwrap_or_else(rand::random);
+    let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+
+    let scene = presets::preset(name, &mut rng, 1920, 1080).ok_or_else(|| {
+        format!(
+            "unknown preset '{}', expected one of {}",
+            name,
+            presets::NAMES.join(", ")
+        )
+    })?;
+    Ok((scene, format!("preset {} {}", name, seed).into_bytes()))
+}
+
+// i/o errors don't say which file they are about
+fn with_path(e: Error, path: &str) -> String {
+    match e {
+        Error::Io(err) => format!("'{}': {}", path, err),
+        e => e.to_string(),
+    }
+}
+
+// Ok(None) when help was requested
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Option<Options>, String> {
+    let mut options = Options {
+        scene: None,
+        preset: None,
+        width: None,
+        height: None,
+        samples: None,
+        noise_threshold: None,
+        min_samples: None,
+        depth: None,
+        min_depth: None,
+        integrator: None,
+        ao_distance: None,
+        progressive: false,
+        snapshot_passes: None,
+        snapshot_interval: None,
+        time_limit: None,
+        checkpoint: None,
+        checkpoint_interval: None,
+        resume: false,
+        threads: None,
+        seed: None,
+        output: "output.jpg".to_string(),
+        format: None,
+        tone_map: None,
+        exposure: None,
+        srgb: false,
+        dither: false,
+        aovs: AovSet::new(),
+        quiet: false,
+    };
+
+    while let Some(arg) = args.next() {
+        // accept --flag=value as well as --flag value
+        let (flag, inline_value) = match arg.find('=') {
+            Some(i) if arg.starts_with("--") => {
+                (arg[..i].to_string(), Some(arg[i + 1..].to_string()))
+            }
+            _ => (arg.clone(), None),
+        };
+        let mut value = || {
+            inline_value
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("'{}' needs a value", flag))
+        };
+
+        match flag.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-q" | "--quiet" => options.quiet = true,
+            "--srgb" => options.srgb = true,
+            "--dither" => options.dither = true,
+            "-s" | "--scene" => options.scene = Some(value()?),
+            "-p" | "--preset" => options.preset = Some(value()?),
+            "-W" | "--width" => options.width = Some(number(&flag, &value()?)?),
+            "-H" | "--height" => options.height = Some(number(&flag, &value()?)?),
+            "-n" | "--samples" => options.samples = Some(number(&flag, &value()?)?),
+            "--noise-threshold" => options.noise_threshold = Some(number(&flag, &value()?)?),
+            "--min-samples" => options.min_samples = Some(number(&flag, &value()?)?),
+            "-d" | "--depth" => options.depth = Some(number(&flag, &value()?)?),
+            "--min-depth" => options.min_depth = Some(number(&flag, &value()?)?),
+            "--integrator" => {
+                let name = value()?;
+                options.integrator =
+                    Some(BuiltinIntegrator::from_name(&name).ok_or_else(|| {
+                        format!(
+                            "unknown integrator '{}', expected one of {}",
+                            name,
+                            integrators::NAMES.join(", ")
+                        )
+                    })?);
+            }
+            "--ao-distance" => options.ao_distance = Some(number(&flag, &value()?)?),
+            "--progressive" => options.progressive = true,
+            "--snapshot-passes" => {
+                options.progressive = true;
+                options.snapshot_passes = Some(number(&flag, &value()?)?);
+            }
+            "--snapshot-seconds" => {
+                options.progressive = true;
+                options.snapshot_interval = Some(seconds(&flag, &value()?)?);
+            }
+            "--time-limit" => {
+                options.progressive = true;
+                options.time_limit = Some(seconds(&flag, &value()?)?);
+            }
+            "--checkpoint" => options.checkpoint = Some(value()?),
+            "--checkpoint-seconds" => {
+                options.checkpoint_interval = Some(seconds(&flag, &value()?)?)
+            }
+            "--resume" => options.resume = true,
+            "-j" | "--threads" => options.threads = Some(number(&flag, &value()?)?),
+            "--seed" => options.seed = Some(number(&flag, &value()?)?),
+            "-o" | "--output" => options.output = value()?,
+            "-f" | "--format" => {
+                let format = value()?;
+                options.format =
+                    Some(OutputFormat::from_extension(&format).map_err(|e| e.to_string())?);
+            }
+            "--tone-map" => {
+                let name = value()?;
+                options.tone_map = Some(ToneMap::from_name(&name).ok_or_else(|| {
+                    format!(
+                        "unknown tone map '{}', expected one of {}",
+                        name,
+                        tonemap::NAMES.join(", ")
+                    )
+                })?);
+            }
+            "--exposure" => options.exposure = Some(number(&flag, &value()?)?),
+            "--aovs" => {
+                for name in value()?.split(',') {
+                    let aov = Aov::from_name(name.trim()).ok_or_else(|| {
+                        format!(
+                            "unknown pass '{}', expected one of {}",
+                            name,
+                            aov::NAMES.join(", ")
+                        )
+                    })?;
+                    options.aovs.insert(aov);
                 }
             }
+            _ => return Err(format!("unknown option '{}'", arg)),
         }
     }
 
-    list.push(Box::new(Sphere::new(
-        Vec3::new(-4., 5., -7.),
-        5.0,
-        Material::Metal(Metal::new(Vec3::new(0.4, 0.2, 0.1), 0.0)),
-    )));
-    list.push(Box::new(Sphere::new(
-        Vec3::new(5.9, 5., -9.),
-        5.0,
-        Material::Dielectric(Dielectric::new(1.5)),
-    )));
-
-    let world = ReflexibleList::new(list);
-
-    let lookfrom = Vec3::new(0.0, 0.5, 7.0);
-    let lookat = Vec3::new(0.0, 0.0, -1.0);
-    let dist_to_focus = (lookfrom - lookat).length();
-    let aperture = 0.0;
-
-    let cam = Camera::new(
-        &lookfrom,
-        &lookat,
-        &Vec3::new(0.0, 1.0, 0.0),
-        30.0,
-        f64::from(nx) / f64::from(ny),
-        aperture,
-        dist_to_focus,
-    );
+    if options.scene.is_some() && options.preset.is_some() {
+        return Err("--scene and --preset can't be used together".to_string());
+    }
+    if options.min_samples.is_some() && options.noise_threshold.is_none() {
+        return Err("--min-samples needs --noise-threshold".to_string());
+    }
+    match options.integrator {
+        None | Some(BuiltinIntegrator::AmbientOcclusion(_)) => (),
+        Some(_) if options.ao_distance.is_some() => {
+            return Err("--ao-distance needs the ambient_occlusion integrator".to_string())
+        }
+        Some(_) => (),
+    }
+    if options.checkpoint.is_none() {
+        if options.resume {
+            return Err("--resume needs --checkpoint".to_string());
+        }
+        if options.checkpoint_interval.is_some() {
+            return Err("--checkpoint-seconds needs --checkpoint".to_string());
+        }
+    } else if options.scene.is_none() && options.seed.is_none() {
+        // the preset would come out different on resume
+        return Err("--checkpoint needs --seed when rendering a preset".to_string());
+    }
+
+    Ok(Some(options))
+}
+
+fn seconds(flag: &str, value: &str) -> Result<Duration, String> {
+    Duration::try_from_secs_f64(number(flag, value)?)
+        .map_err(|_| format!("invalid value '{}' for '{}'", value, flag))
+}
+
+fn number<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value '{}' for '{}'", value, flag))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<Options>, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn options(args: &[&str]) -> Options {
+        match parse(args) {
+            Ok(Some(options)) => options,
+            Ok(None) => panic!("help for {:?}", args),
+            Err(e) => panic!("{} for {:?}", e, args),
+        }
+    }
+
+    fn error(args: &[&str]) -> String {
+        match parse(args) {
+            Err(e) => e,
+            Ok(_) => panic!("accepted {:?}", args),
+        }
+    }
+
+    #[test]
+    fn values() {
+        let o = options(&[
+            "-s",
+            "scene.toml",
+            "--width=320",
+            "-H",
+            "200",
+            "--samples",
+            "64",
+            "--noise-threshold",
+            "0.05",
+            "--seed=7",
+            "-o",
+            "out.exr",
+            "--exposure",
+            "-1.5",
+        ]);
+        assert_eq!(o.scene.as_deref(), Some("scene.toml"));
+        assert_eq!((o.width, o.height), (Some(320), Some(200)));
+        assert_eq!(o.samples, Some(64));
+        assert_eq!(o.noise_threshold, Some(0.05));
+        assert_eq!(o.seed, Some(7));
+        assert_eq!(o.output, "out.exr");
+        assert_eq!(o.exposure, Some(-1.5));
+        assert!(!o.progressive && !o.quiet);
+
+        let o = options(&[]);
+        assert_eq!(o.output, "output.jpg");
+        assert!(o.scene.is_none() && o.samples.is_none());
 
-    ray_tracer::ray_tracer(&world, &cam, nx, ny, "output.jpg");
+        assert!(parse(&["-q", "--help"]).unwrap().is_none());
+    }
+
+    #[test]
+    fn bad_values() {
+        assert_eq!(
+            error(&["--width", "wide"]),
+            "invalid value 'wide' for '--width'"
+        );
+        assert_eq!(error(&["--samples"]), "'--samples' needs a value");
+        assert_eq!(
+            error(&["--time-limit", "-3"]),
+            "invalid value '-3' for '--time-limit'"
+        );
+        assert!(error(&["--tone-map", "filmic"]).starts_with("unknown tone map 'filmic'"));
+        assert!(error(&["--aovs", "depth,shadow"]).starts_with("unknown pass 'shadow'"));
+    }
+
+    #[test]
+    fn unknown_flags() {
+        assert_eq!(error(&["--fast"]), "unknown option '--fast'");
+        assert_eq!(error(&["--fast=yes"]), "unknown option '--fast=yes'");
+        assert_eq!(error(&["scene.toml"]), "unknown option 'scene.toml'");
+    }
+
+    #[test]
+    fn progressive() {
+        let o = options(&["--snapshot-passes", "8"]);
+        assert!(o.progressive);
+        assert_eq!(o.snapshot_passes, Some(8));
+        assert!(options(&["--snapshot-seconds", "30"]).progressive);
+        assert!(options(&["--time-limit=600"]).progressive);
+        assert!(options(&["--progressive"]).progressive);
+        assert_eq!(options(&["--progressive"]).snapshot_passes, None);
+    }
+
+    #[test]
+    fn integrators() {
+        let o = options(&["--integrator", "normal"]);
+        assert!(o.integrator.is_some() && o.ao_distance.is_none());
+        let o = options(&["--integrator=ambient_occlusion", "--ao-distance", "2.5"]);
+        assert_eq!(o.ao_distance, Some(2.5));
+        assert_eq!(options(&["--ao-distance=4"]).ao_distance, Some(4.0));
+        for args in &[
+            ["--integrator", "uv", "--ao-distance", "2"],
+            ["--integrator", "path", "--ao-distance", "2"],
+            ["--ao-distance", "2", "--integrator", "path"],
+        ] {
+            assert_eq!(
+                error(args),
+                "--ao-distance needs the ambient_occlusion integrator"
+            );
+        }
+        assert_eq!(
+            error(&["--ao-distance", "far"]),
+            "invalid value 'far' for '--ao-distance'"
+        );
+    }
+
+    #[test]
+    fn combinations() {
+        assert_eq!(
+            error(&["--scene", "a.toml", "--preset", "metal"]),
+            "--scene and --preset can't be used together"
+        );
+        assert_eq!(
+            error(&["--min-samples", "4"]),
+            "--min-samples needs --noise-threshold"
+        );
+        assert_eq!(error(&["--resume"]), "--resume needs --checkpoint");
+        assert_eq!(
+            error(&["--checkpoint", "render.ck"]),
+            "--checkpoint needs --seed when rendering a preset"
+        );
+        let o = options(&["--checkpoint", "render.ck", "--seed", "1", "--resume"]);
+        assert!(o.resume);
+    }
 }
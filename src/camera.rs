@@ -1,6 +1,6 @@
 use super::ray::Ray;
+use super::sampler::Sampler;
 use super::vec3::Vec3;
-use rand::Rng;
 
 pub struct Camera {
     lower_left_corner: Vec3,
@@ -11,6 +11,8 @@ pub struct Camera {
     v: Vec3,
     _w: Vec3,
     lens_radius: f64,
+    shutter_open: f64,
+    shutter_close: f64,
 }
 
 impl Camera {
@@ -45,29 +47,48 @@ impl Camera {
             u,
             v,
             _w: w,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
         }
     }
 
-    pub fn get_ray(&self, s: f64, t: f64) -> Ray {
-        let rd = self.lens_radius * random_in_unit_disk();
+    // rays are cast at random times between `open` and `close`, which blurs moving objects
+    pub fn with_shutter(mut self, open: f64, close: f64) -> Camera {
+        self.shutter_open = open;
+        self.shutter_close = close;
+        self
+    }
+
+    // how far in front of the camera things are in focus
+    pub fn focus_distance(&self) -> f64 {
+        (self.lower_left_corner + 0.5 * self.horizontal + 0.5 * self.vertical - self.origin)
+            .length()
+    }
+
+    pub fn get_ray(&self, s: f64, t: f64, sampler: &mut Sampler) -> Ray {
+        let rd = self.lens_radius * random_in_unit_disk(sampler);
         let offset = self.u * rd.x + self.v * rd.y;
+        // a closed shutter takes no sample, so still images come out the same
+        let time = if self.shutter_close > self.shutter_open {
+            self.shutter_open + sampler.next_f64() * (self.shutter_close - self.shutter_open)
+        } else {
+            self.shutter_open
+        };
 
         Ray::new(
-            self.origin.clone() - offset,
-            self.lower_left_corner.clone() + s * self.horizontal + t * self.vertical
-                - self.origin.clone()
-                - offset,
+            self.origin - offset,
+            self.lower_left_corner + s * self.horizontal + t * self.vertical - self.origin - offset,
         )
+        .with_time(time)
     }
 }
 
-fn random_in_unit_disk() -> Vec3 {
+fn random_in_unit_disk(sampler: &mut Sampler) -> Vec3 {
     let mut p: Vec3;
-    let mut rng = rand::thread_rng();
 
     loop {
-        let x: f64 = rng.gen();
-        let y: f64 = rng.gen();
+        let x: f64 = sampler.next_f64();
+        let y: f64 = sampler.next_f64();
 
         p = 2.0 * Vec3::new(x, y, 0.0) - Vec3::new(1.0, 1.0, 0.0);
 
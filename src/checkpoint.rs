@@ -0,0 +1,424 @@
+// render checkpoints: the accumulated samples of every pixel, so that an interrupted render
+// can be resumed. samplers are derived from the seed, the pixel and the sample index, so
+// the sample counts are all the random state there is, and a resumed render comes out the
+// same as an uninterrupted one.
+//
+// little endian, a header followed by the pixels row by row from the top left:
+//   "RTCK", version u32, width u32, height u32, aov bits u8
+//   settings: seed u64, max_depth u32, min_depth u32, t_min f64, background, adaptive sampling,
+//             integrator (the settings' one, not one passed to `accumulate_with`)
+//   scene u64, see `scene_key`
+//   per pixel: sum 3 x f64, sample count u32, luminance sum f64, squared luminance sum f64,
+//              then 3 x f64 for each aov slot if any aovs are on
+
+use crate::accumulator::{Accumulator, AOV_COUNT};
+use crate::adaptive::PixelStats;
+use crate::aov::{AovSet, Aovs};
+use crate::error::Error;
+use crate::framebuffer::Framebuffer;
+use crate::integrators::debug::DebugView;
+use crate::integrators::BuiltinIntegrator;
+use crate::progress::{Progress, ProgressObserver};
+use crate::scene::CameraParams;
+use crate::settings::{Background, RenderSettings};
+use crate::vec3::Vec3;
+
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const MAGIC: &[u8; 4] = b"RTCK";
+const VERSION: u32 = 5;
+
+// `scene` is the `scene_key` of what is rendered
+pub fn write<W: Write>(
+    w: &mut W,
+    acc: &Accumulator,
+    settings: &RenderSettings,
+    scene: u64,
+) -> io::Result<()> {
+    w.write_all(MAGIC)?;
+    put_u32(w, VERSION)?;
+    put_u32(w, acc.width())?;
+    put_u32(w, acc.height())?;
+    w.write_all(&[acc.aovs().bits()])?;
+    w.write_all(&settings_key(settings))?;
+    w.write_all(&scene.to_le_bytes())?;
+
+    for y in 0..acc.height() {
+        for x in 0..acc.width() {
+            let pixel = acc.get(x, y);
+            put_vec3(w, pixel.sum)?;
+
+            let (count, sum, sum_sq) = pixel.stats.parts();
+            put_u32(w, count)?;
+            put_f64(w, sum)?;
+            put_f64(w, sum_sq)?;
+
+            if !acc.aovs().is_empty() {
+                for v in pixel.aov_sums.iter() {
+                    put_vec3(w, *v)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// reads a checkpoint of a `width` x `height` render written with the same sampling settings
+// as `settings`, of the scene with the `scene_key` `scene`
+pub fn read<R: Read>(
+    r: &mut R,
+    width: u32,
+    height: u32,
+    settings: &RenderSettings,
+    scene: u64,
+) -> Result<Accumulator, Error> {
+    let mut magic = [0; 4];
+    r.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(Error::Checkpoint("not a checkpoint file".to_string()));
+    }
+    let version = get_u32(r)?;
+    if version != VERSION {
+        return Err(Error::Checkpoint(format!(
+            "unsupported version {}",
+            version
+        )));
+    }
+
+    // checked before anything is allocated, the header could say anything
+    let size = (get_u32(r)?, get_u32(r)?);
+    if size != (width, height) {
+        return Err(Error::Checkpoint(format!(
+            "it is a {}x{} render, not {}x{}",
+            size.0, size.1, width, height
+        )));
+    }
+    if (width as usize).checked_mul(height as usize).is_none() {
+        return Err(Error::Checkpoint(format!(
+            "a {}x{} render is too large",
+            width, height
+        )));
+    }
+    let mut bits = [0];
+    r.read_exact(&mut bits)?;
+    let aovs = AovSet::from_bits(bits[0])
+        .ok_or_else(|| Error::Checkpoint(format!("unknown aovs {:#x}", bits[0])))?;
+    if aovs != settings.aovs {
+        return Err(Error::Checkpoint(
+            "it was made with different aovs".to_string(),
+        ));
+    }
+
+    let mut key = vec![0; settings_key(settings).len()];
+    r.read_exact(&mut key)?;
+    if key != settings_key(settings) {
+        return Err(Error::Checkpoint(
+            "it was made with a different seed, depth, background or adaptive sampling".to_string(),
+        ));
+    }
+    let mut key = [0; 8];
+    r.read_exact(&mut key)?;
+    if u64::from_le_bytes(key) != scene {
+        return Err(Error::Checkpoint(
+            "it was made of a different scene or from another camera".to_string(),
+        ));
+    }
+
+    let mut acc = Accumulator::new(width, height, aovs);
+    for y in 0..height {
+        for x in 0..width {
+            let mut pixel = acc.get(x, y);
+            pixel.sum = get_vec3(r)?;
+
+            let count = get_u32(r)?;
+            let sum = get_f64(r)?;
+            let sum_sq = get_f64(r)?;
+            pixel.stats = PixelStats::from_parts(count, sum, sum_sq);
+
+            if !aovs.is_empty() {
+                for slot in 0..AOV_COUNT {
+                    pixel.aov_sums[slot] = get_vec3(r)?;
+                }
+            }
+            acc.set(x, y, &pixel);
+        }
+    }
+
+    Ok(acc)
+}
+
+// writes to a temporary file first, so a crash while saving leaves the last checkpoint
+pub fn save<P: AsRef<Path>>(
+    path: P,
+    acc: &Accumulator,
+    settings: &RenderSettings,
+    scene: u64,
+) -> Result<(), Error> {
+    let path = path.as_ref();
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+
+    let mut out = BufWriter::new(File::create(&tmp)?);
+    write(&mut out, acc, settings, scene)?;
+    out.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    fs::rename(&tmp, path)?;
+
+    Ok(())
+}
+
+pub fn load<P: AsRef<Path>>(
+    path: P,
+    width: u32,
+    height: u32,
+    settings: &RenderSettings,
+    scene: u64,
+) -> Result<Accumulator, Error> {
+    let mut input = BufReader::new(File::open(path)?);
+    read(&mut input, width, height, settings, scene).map_err(|e| match e {
+        Error::Io(ref err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+            Error::Checkpoint("the file is truncated".to_string())
+        }
+        e => e,
+    })
+}
+
+// a fingerprint of what is rendered: the scene's `description`, like the contents of its
+// file, and where the camera looks from. fnv-1a, which unlike std's hasher stays the same
+// between builds.
+pub fn scene_key(description: &[u8], camera: &CameraParams) -> u64 {
+    let mut bytes = description.to_vec();
+    let vectors = [camera.look_from, camera.look_at, camera.vup];
+    for v in vectors.iter() {
+        for c in &[v.x, v.y, v.z] {
+            bytes.extend_from_slice(&c.to_le_bytes());
+        }
+    }
+    let numbers = [
+        camera.vfov,
+        camera.aperture,
+        camera.focus_dist,
+        camera.shutter_open,
+        camera.shutter_close,
+    ];
+    for n in numbers.iter() {
+        bytes.extend_from_slice(&n.to_le_bytes());
+    }
+
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash: u64, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+// the settings that change which samples are taken and what they add up to
+fn settings_key(settings: &RenderSettings) -> Vec<u8> {
+    let mut key = Vec::new();
+    key.extend_from_slice(&settings.seed.to_le_bytes());
+    key.extend_from_slice(&settings.max_depth.to_le_bytes());
+    key.extend_from_slice(&settings.min_depth.to_le_bytes());
+    key.extend_from_slice(&settings.t_min.to_le_bytes());
+
+    let (tag, a, b) = match settings.background {
+        Background::Gradient { bottom, top } => (0u8, bottom, top),
+        Background::Solid(color) => (1u8, color, color),
+    };
+    key.push(tag);
+    for v in &[a.x, a.y, a.z, b.x, b.y, b.z] {
+        key.extend_from_slice(&v.to_le_bytes());
+    }
+
+    match settings.adaptive {
+        Some(adaptive) => {
+            key.push(1);
+            key.extend_from_slice(&adaptive.threshold.to_le_bytes());
+            key.extend_from_slice(&adaptive.min_samples.to_le_bytes());
+        }
+        None => key.push(0),
+    }
+
+    let (tag, distance) = match settings.integrator {
+        BuiltinIntegrator::Path(_) => (0u8, 0.0),
+        // 0 for the focus distance, which a set distance can't be
+        BuiltinIntegrator::AmbientOcclusion(ao) => (1u8, ao.distance().unwrap_or(0.0)),
+        BuiltinIntegrator::Debug(DebugView::Normal) => (2u8, 0.0),
+        BuiltinIntegrator::Debug(DebugView::Albedo) => (3u8, 0.0),
+        BuiltinIntegrator::Debug(DebugView::Uv) => (4u8, 0.0),
+    };
+    key.push(tag);
+    key.extend_from_slice(&distance.to_le_bytes());
+
+    key
+}
+
+// saves the render's samples to `path` whenever it checkpoints, passing everything on to
+// `inner`. a failed save is reported by `finish`, the render goes on.
+pub struct CheckpointWriter<'a> {
+    inner: &'a dyn ProgressObserver,
+    path: PathBuf,
+    settings: RenderSettings,
+    scene: u64,
+    error: Mutex<Option<Error>>,
+}
+
+impl<'a> CheckpointWriter<'a> {
+    pub fn new<P: AsRef<Path>>(
+        inner: &'a dyn ProgressObserver,
+        path: P,
+        settings: &RenderSettings,
+        scene: u64,
+    ) -> CheckpointWriter<'a> {
+        CheckpointWriter {
+            inner,
+            path: path.as_ref().to_path_buf(),
+            settings: *settings,
+            scene,
+            error: Mutex::new(None),
+        }
+    }
+
+    pub fn finish(self) -> Result<(), Error> {
+        match self.error.into_inner().unwrap() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'a> ProgressObserver for CheckpointWriter<'a> {
+    fn on_progress(&self, progress: &Progress) {
+        self.inner.on_progress(progress);
+    }
+
+    fn on_snapshot(&self, fb: &Framebuffer, aovs: &Aovs, progress: &Progress) {
+        self.inner.on_snapshot(fb, aovs, progress);
+    }
+
+    fn on_checkpoint(&self, acc: &Accumulator, progress: &Progress) {
+        self.inner.on_checkpoint(acc, progress);
+
+        if let Err(e) = save(&self.path, acc, &self.settings, self.scene) {
+            self.error.lock().unwrap().get_or_insert(e);
+        }
+    }
+
+    fn on_finish(&self, progress: &Progress, cancelled: bool) {
+        self.inner.on_finish(progress, cancelled);
+    }
+}
+
+fn put_u32<W: Write>(w: &mut W, v: u32) -> io::Result<()> {
+    w.write_all(&v.to_le_bytes())
+}
+
+fn put_f64<W: Write>(w: &mut W, v: f64) -> io::Result<()> {
+    w.write_all(&v.to_le_bytes())
+}
+
+fn put_vec3<W: Write>(w: &mut W, v: Vec3) -> io::Result<()> {
+    put_f64(w, v.x)?;
+    put_f64(w, v.y)?;
+    put_f64(w, v.z)
+}
+
+fn get_u32<R: Read>(r: &mut R) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    r.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn get_f64<R: Read>(r: &mut R) -> io::Result<f64> {
+    let mut bytes = [0; 8];
+    r.read_exact(&mut bytes)?;
+    Ok(f64::from_le_bytes(bytes))
+}
+
+fn get_vec3<R: Read>(r: &mut R) -> io::Result<Vec3> {
+    Ok(Vec3::new(get_f64(r)?, get_f64(r)?, get_f64(r)?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let settings = RenderSettings {
+            seed: 3,
+            ..Default::default()
+        };
+        let mut acc = Accumulator::new(3, 2, AovSet::new());
+        let mut pixel = acc.get(2, 1);
+        pixel.sum = Vec3::new(0.1, 2.5, -0.0);
+        pixel.stats.add(pixel.sum);
+        acc.set(2, 1, &pixel);
+
+        let mut bytes = Vec::new();
+        write(&mut bytes, &acc, &settings, 7).unwrap();
+        assert_eq!(read(&mut &bytes[..], 3, 2, &settings, 7).unwrap(), acc);
+
+        let reseeded = RenderSettings {
+            seed: 4,
+            ..settings
+        };
+        match read(&mut &bytes[..], 3, 2, &reseeded, 7) {
+            Err(Error::Checkpoint(_)) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        match read(&mut &bytes[..], 3, 2, &settings, 8) {
+            Err(Error::Checkpoint(message)) => {
+                assert_eq!(
+                    message,
+                    "it was made of a different scene or from another camera"
+                )
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        match read(&mut &bytes[..], 2, 3, &settings, 7) {
+            Err(Error::Checkpoint(message)) => assert_eq!(message, "it is a 3x2 render, not 2x3"),
+            other => panic!("unexpected {:?}", other),
+        }
+        // a corrupt size is caught before the pixels are allocated
+        bytes[8..16].copy_from_slice(&[0xff; 8]);
+        match read(&mut &bytes[..], 3, 2, &settings, 7) {
+            Err(Error::Checkpoint(message)) => {
+                assert_eq!(message, "it is a 4294967295x4294967295 render, not 3x2")
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        match read(&mut &b"RTCX"[..], 3, 2, &settings, 7) {
+            Err(Error::Checkpoint(message)) => assert_eq!(message, "not a checkpoint file"),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn scene_keys() {
+        let camera = CameraParams {
+            look_from: Vec3::new(13.0, 2.0, 3.0),
+            look_at: Vec3::new(0.0, 0.0, 0.0),
+            vup: Vec3::new(0.0, 1.0, 0.0),
+            vfov: 20.0,
+            aperture: 0.1,
+            focus_dist: 10.0,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
+        };
+        let key = scene_key(b"[camera]", &camera);
+
+        assert_eq!(key, scene_key(b"[camera]", &camera));
+        assert_ne!(key, scene_key(b"[camera] ", &camera));
+        let moved = CameraParams {
+            look_from: Vec3::new(13.0, 2.5, 3.0),
+            ..camera
+        };
+        assert_ne!(key, scene_key(b"[camera]", &moved));
+        let zoomed = CameraParams {
+            vfov: 30.0,
+            ..camera
+        };
+        assert_ne!(key, scene_key(b"[camera]", &zoomed));
+    }
+}
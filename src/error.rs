@@ -0,0 +1,70 @@
+use std::error;
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum Error {
+    InvalidSettings(String),
+    UnsupportedFormat(String),
+    InvalidMesh(String),
+    Parse {
+        file: String,
+        line: usize,
+        message: String,
+    },
+    Scene {
+        file: String,
+        entry: String,
+        message: String,
+    },
+    Checkpoint(String),
+    Io(io::Error),
+    Image(image::ImageError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::InvalidSettings(ref msg) => write!(f, "invalid render settings: {}", msg),
+            Error::UnsupportedFormat(ref ext) => {
+                write!(f, "unsupported output format '{}'", ext)
+            }
+            Error::InvalidMesh(ref msg) => write!(f, "invalid mesh: {}", msg),
+            Error::Parse {
+                ref file,
+                line,
+                ref message,
+            } => write!(f, "{}:{}: {}", file, line, message),
+            Error::Scene {
+                ref file,
+                ref entry,
+                ref message,
+            } => write!(f, "{}: {}: {}", file, entry, message),
+            Error::Checkpoint(ref msg) => write!(f, "invalid checkpoint: {}", msg),
+            Error::Io(ref err) => write!(f, "i/o error: {}", err),
+            Error::Image(ref err) => write!(f, "image encoding error: {}", err),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Io(ref err) => Some(err),
+            Error::Image(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
+impl From<image::ImageError> for Error {
+    fn from(err: image::ImageError) -> Error {
+        Error::Image(err)
+    }
+}
@@ -0,0 +1,74 @@
+use crate::tonemap::ToneMapping;
+use crate::vec3::Vec3;
+
+// linear rgb radiance, stored row by row from the top left pixel
+#[derive(Clone, Debug, PartialEq)]
+pub struct Framebuffer {
+    width: u32,
+    height: u32,
+    pixels: Vec<Vec3>,
+}
+
+impl Framebuffer {
+    pub fn new(width: u32, height: u32) -> Framebuffer {
+        Framebuffer {
+            width,
+            height,
+            pixels: vec![Vec3::new(0.0, 0.0, 0.0); (width * height) as usize],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> Vec3 {
+        self.pixels[self.index(x, y)]
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, col: Vec3) {
+        let index = self.index(x, y);
+        self.pixels[index] = col;
+    }
+
+    pub fn pixels(&self) -> &[Vec3] {
+        &self.pixels
+    }
+
+    pub fn to_rgb_f32(&self) -> Vec<f32> {
+        self.pixels
+            .iter()
+            .flat_map(|p| vec![p.x as f32, p.y as f32, p.z as f32])
+            .collect()
+    }
+
+    pub fn to_rgb8(&self, tone_mapping: &ToneMapping) -> image::RgbImage {
+        image::ImageBuffer::from_fn(self.width, self.height, |x, y| {
+            image::Rgb(tone_mapping.to_rgb8(self.get(x, y), x, y))
+        })
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        (y * self.width + x) as usize
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn to_rgb8() {
+        let mut fb = Framebuffer::new(2, 1);
+        fb.set(1, 0, Vec3::new(4.0, 0.25, -1.0));
+
+        let img = fb.to_rgb8(&ToneMapping::default());
+
+        assert_eq!(*img.get_pixel(0, 0), image::Rgb([0, 0, 0]));
+        assert_eq!(*img.get_pixel(1, 0), image::Rgb([255, 127, 0]));
+    }
+}
@@ -0,0 +1,143 @@
+use crate::error::Error;
+use crate::integrators::{Context, Integrator};
+use crate::random_unit_vector;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::vec3::Vec3;
+
+// white where a ray from the first hit, in a random direction around the normal, gets
+// `distance` away without hitting anything and black where it doesn't. averaged over the
+// samples that shows how open every surface is, whatever the lights and materials.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AmbientOcclusion {
+    // None for the camera's focus distance, which keeps closed rooms from going black
+    distance: Option<f64>,
+}
+
+impl AmbientOcclusion {
+    pub fn new() -> AmbientOcclusion {
+        AmbientOcclusion { distance: None }
+    }
+
+    pub fn with_distance(mut self, distance: f64) -> AmbientOcclusion {
+        self.distance = Some(distance);
+        self
+    }
+
+    pub fn distance(&self) -> Option<f64> {
+        self.distance
+    }
+}
+
+impl Integrator for AmbientOcclusion {
+    fn color(&self, r: &Ray, context: &Context, rays: &mut u64, sampler: &mut Sampler) -> Vec3 {
+        let open = Vec3::new(1.0, 1.0, 1.0);
+        let t_min = context.settings.t_min;
+
+        *rays += 1;
+        let rec = match context.world.hit(r, t_min, f64::MAX) {
+            Some(rec) => rec,
+            None => return open,
+        };
+
+        // cosine weighted, like the light a diffuse surface takes in
+        let mut direction = rec.normal + random_unit_vector(sampler);
+        if direction.squared_length() < 1e-12 {
+            direction = rec.normal;
+        }
+
+        *rays += 1;
+        let probe = Ray::new(rec.p, direction).with_time(r.time);
+        let distance = self
+            .distance
+            .unwrap_or_else(|| context.camera.focus_distance());
+        let t_max = distance / direction.length();
+        match context.world.hit(&probe, t_min, t_max) {
+            Some(_) => Vec3::new(0.0, 0.0, 0.0),
+            None => open,
+        }
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        match self.distance {
+            Some(distance) if distance.is_nan() || distance <= 0.0 => {
+                Err(Error::InvalidSettings(format!(
+                    "ambient occlusion distance must be positive, got {}",
+                    distance
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::camera::Camera;
+    use crate::lights::LightList;
+    use crate::materials::lambertian::Lambertian;
+    use crate::materials::Material;
+    use crate::reflexible::plane::Plane;
+    use crate::reflexible::{Reflexible, ReflexibleList};
+    use crate::settings::RenderSettings;
+
+    #[test]
+    fn occlusion() {
+        // a floor under a ceiling one unit up
+        let material = || Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)));
+        let list: Vec<Box<dyn Reflexible>> = vec![
+            Box::new(Plane::new(
+                Vec3::new(0.0, 0.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+                material(),
+            )),
+            Box::new(Plane::new(
+                Vec3::new(0.0, 1.0, 0.0),
+                Vec3::new(0.0, -1.0, 0.0),
+                material(),
+            )),
+        ];
+        let world = ReflexibleList::new(list);
+        let lights = LightList::new(&world);
+        let settings = RenderSettings::default();
+        // focused two units away
+        let camera = Camera::new(
+            &Vec3::new(0.0, 0.5, 0.0),
+            &Vec3::new(2.0, 0.5, 0.0),
+            &Vec3::new(0.0, 1.0, 0.0),
+            90.0,
+            1.0,
+            0.0,
+            2.0,
+        );
+        assert!((camera.focus_distance() - 2.0).abs() < 1e-12);
+        let context = Context {
+            world: &world,
+            lights: &lights,
+            camera: &camera,
+            settings: &settings,
+        };
+
+        let r = Ray::new(Vec3::new(0.0, 0.5, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        let mut sampler = Sampler::new(1);
+        let mut rays = 0;
+        let mut mean = |ao: AmbientOcclusion| {
+            (0..1000).fold(0.0, |sum, _| {
+                sum + ao.color(&r, &context, &mut rays, &mut sampler).x
+            }) / 1000.0
+        };
+
+        assert_eq!(
+            mean(AmbientOcclusion::new().with_distance(f64::INFINITY)),
+            0.0
+        );
+        assert_eq!(mean(AmbientOcclusion::new().with_distance(0.5)), 1.0);
+        // only rays more than 60 degrees from the normal get two units away, which cosine
+        // weighting picks a quarter of the time
+        let two = mean(AmbientOcclusion::new().with_distance(2.0));
+        assert!((two - 0.25).abs() < 0.05);
+        let focused = mean(AmbientOcclusion::new());
+        assert!((focused - 0.25).abs() < 0.05);
+    }
+}
@@ -0,0 +1,31 @@
+use crate::integrators::{Context, Integrator};
+use crate::materials::Scatterable;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::vec3::Vec3;
+
+// shows what the first hit is like instead of how it's lit, black where rays miss
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DebugView {
+    // mapped from [-1, 1] into [0, 1]
+    Normal,
+    Albedo,
+    // red for u and green for v
+    Uv,
+}
+
+impl Integrator for DebugView {
+    fn color(&self, r: &Ray, context: &Context, rays: &mut u64, _sampler: &mut Sampler) -> Vec3 {
+        *rays += 1;
+        let rec = match context.world.hit(r, context.settings.t_min, f64::MAX) {
+            Some(rec) => rec,
+            None => return Vec3::new(0.0, 0.0, 0.0),
+        };
+
+        match *self {
+            DebugView::Normal => 0.5 * (Vec3::unit_vector(rec.normal) + Vec3::new(1.0, 1.0, 1.0)),
+            DebugView::Albedo => rec.material.albedo(&rec),
+            DebugView::Uv => Vec3::new(rec.u, rec.v, 0.0),
+        }
+    }
+}
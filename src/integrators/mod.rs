@@ -0,0 +1,84 @@
+use crate::camera::Camera;
+use crate::error::Error;
+use crate::lights::LightList;
+use crate::ray::Ray;
+use crate::reflexible::Reflexible;
+use crate::sampler::Sampler;
+use crate::settings::RenderSettings;
+use crate::vec3::Vec3;
+
+pub mod ambient_occlusion;
+pub mod debug;
+pub mod path;
+
+pub const NAMES: [&str; 5] = ["path", "ambient_occlusion", "normal", "albedo", "uv"];
+
+// what integrators trace rays against
+pub struct Context<'a, 'w> {
+    pub world: &'w dyn Reflexible,
+    // the world's lights
+    pub lights: &'a LightList<'w>,
+    pub camera: &'a Camera,
+    pub settings: &'a RenderSettings,
+}
+
+// turns camera rays into colors. renders use `RenderSettings::integrator`, other
+// integrators can be plugged into `accumulate_with`.
+pub trait Integrator: Send + Sync {
+    // the light coming back along `r`, adding the rays traced for it to `rays`
+    fn color(&self, r: &Ray, context: &Context, rays: &mut u64, sampler: &mut Sampler) -> Vec3;
+
+    // checked before rendering with it
+    fn validate(&self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+// the integrators that come with the crate
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BuiltinIntegrator {
+    Path(path::PathTracer),
+    AmbientOcclusion(ambient_occlusion::AmbientOcclusion),
+    Debug(debug::DebugView),
+}
+
+impl BuiltinIntegrator {
+    pub fn from_name(name: &str) -> Option<BuiltinIntegrator> {
+        match name {
+            "path" => Some(BuiltinIntegrator::Path(path::PathTracer)),
+            "ambient_occlusion" => Some(BuiltinIntegrator::AmbientOcclusion(
+                ambient_occlusion::AmbientOcclusion::new(),
+            )),
+            "normal" => Some(BuiltinIntegrator::Debug(debug::DebugView::Normal)),
+            "albedo" => Some(BuiltinIntegrator::Debug(debug::DebugView::Albedo)),
+            "uv" => Some(BuiltinIntegrator::Debug(debug::DebugView::Uv)),
+            _ => None,
+        }
+    }
+}
+
+impl Default for BuiltinIntegrator {
+    fn default() -> BuiltinIntegrator {
+        BuiltinIntegrator::Path(path::PathTracer)
+    }
+}
+
+impl Integrator for BuiltinIntegrator {
+    fn color(&self, r: &Ray, context: &Context, rays: &mut u64, sampler: &mut Sampler) -> Vec3 {
+        match *self {
+            BuiltinIntegrator::Path(ref inner) => inner.color(r, context, rays, sampler),
+            BuiltinIntegrator::AmbientOcclusion(ref inner) => {
+                inner.color(r, context, rays, sampler)
+            }
+            BuiltinIntegrator::Debug(ref inner) => inner.color(r, context, rays, sampler),
+        }
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        match *self {
+            BuiltinIntegrator::Path(ref inner) => inner.validate(),
+            BuiltinIntegrator::AmbientOcclusion(ref inner) => inner.validate(),
+            BuiltinIntegrator::Debug(ref inner) => inner.validate(),
+        }
+    }
+}
@@ -0,0 +1,357 @@
+use crate::integrators::{Context, Integrator};
+use crate::materials::Scatterable;
+use crate::ray::Ray;
+use crate::reflexible::HitRecord;
+use crate::sampler::Sampler;
+use crate::vec3::Vec3;
+
+use std::ptr;
+
+// follows rays one bounce at a time, sampling the lights at every bounce that isn't
+// specular and combining those samples with the bounced rays by multiple importance
+// sampling. paths end at `max_depth`, or at random past `min_depth`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PathTracer;
+
+impl Integrator for PathTracer {
+    fn color(&self, r: &Ray, context: &Context, rays: &mut u64, sampler: &mut Sampler) -> Vec3 {
+        let settings = context.settings;
+
+        let mut color = Vec3::new(0.0, 0.0, 0.0);
+        // how much of the light found further along makes it back to the camera
+        let mut throughput = Vec3::new(1.0, 1.0, 1.0);
+        let mut r = *r;
+        // the density the last bounce picked r's direction with, 0 for camera rays and
+        // specular bounces. hits on the lights are weighted against having sampled them
+        // directly there.
+        let mut bsdf_pdf = 0.0;
+        let mut depth = 0;
+
+        loop {
+            *rays += 1;
+            let rec = match context.world.hit(&r, settings.t_min, f64::MAX) {
+                Some(rec) => rec,
+                None => {
+                    color = color + throughput * settings.background.color(&r);
+                    break;
+                }
+            };
+
+            let emitted = rec.material.emitted(&rec) * emission_weight(context, &r, &rec, bsdf_pdf);
+            color = color + throughput * emitted;
+            if depth >= settings.max_depth {
+                break;
+            }
+
+            let scattered = rec.material.scatter(&r, &rec, sampler);
+            let specular = rec.material.is_specular();
+            if !specular {
+                color = color + throughput * direct_light(context, &r, &rec, rays, sampler);
+            }
+            let (attenuation, scattered) = match scattered {
+                Some(scattered) => scattered,
+                None => break,
+            };
+
+            throughput = throughput * attenuation;
+            bsdf_pdf = if specular {
+                0.0
+            } else {
+                rec.material.pdf(&r, &rec, scattered.direction)
+            };
+            r = scattered;
+            depth += 1;
+
+            // past `min_depth` paths are ended at random, the survivors making up for them
+            if depth >= settings.min_depth {
+                let survival = throughput.x.max(throughput.y).max(throughput.z).min(1.0);
+                if sampler.next_f64() >= survival {
+                    break;
+                }
+                throughput = throughput / survival;
+            }
+        }
+
+        color
+    }
+}
+
+// the share of a hit's light that `r` finding it counts for, the rest comes from sampling
+// the light directly
+fn emission_weight(context: &Context, r: &Ray, rec: &HitRecord, bsdf_pdf: f64) -> f64 {
+    if bsdf_pdf <= 0.0 {
+        return 1.0;
+    }
+    match context.lights.find(rec.material, r) {
+        Some(light) => {
+            let light_pdf = light.pdf(r.origin, r.direction) / context.lights.len() as f64;
+            power_heuristic(bsdf_pdf, light_pdf)
+        }
+        None => 1.0,
+    }
+}
+
+// the light reaching a hit straight from a random light, with one shadow ray
+fn direct_light(
+    context: &Context,
+    r: &Ray,
+    rec: &HitRecord,
+    rays: &mut u64,
+    sampler: &mut Sampler,
+) -> Vec3 {
+    let black = Vec3::new(0.0, 0.0, 0.0);
+    let (light, picked) = match context.lights.pick(sampler) {
+        Some(light) => light,
+        None => return black,
+    };
+    let (direction, pdf) = match light.sample(rec.p, sampler) {
+        Some(sample) => sample,
+        None => return black,
+    };
+    let f = rec.material.eval(r, rec, direction);
+    if f == black {
+        return black;
+    }
+
+    *rays += 1;
+    let shadow = Ray::new(rec.p, direction).with_time(r.time);
+    match context.world.hit(&shadow, context.settings.t_min, f64::MAX) {
+        // anything else in between casts a shadow
+        Some(hit) if ptr::eq(hit.material, light.material()) => {
+            let light_pdf = picked * pdf;
+            let weight = power_heuristic(light_pdf, rec.material.pdf(r, rec, direction));
+            f * hit.material.emitted(&hit) * (weight / light_pdf)
+        }
+        _ => black,
+    }
+}
+
+// the weight of a sample taken with density `pdf` when another strategy could have taken
+// it with density `other`
+fn power_heuristic(pdf: f64, other: f64) -> f64 {
+    pdf * pdf / (pdf * pdf + other * other)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::camera::Camera;
+    use crate::lights::LightList;
+    use crate::materials::diffuse_light::DiffuseLight;
+    use crate::materials::Material;
+    use crate::materials::{dielectric::Dielectric, lambertian::Lambertian, metal::Metal};
+    use crate::reflexible::plane::Plane;
+    use crate::reflexible::sphere::Sphere;
+    use crate::reflexible::{Reflexible, ReflexibleList};
+    use crate::settings::{Background, RenderSettings};
+
+    // the mean color of `samples` paths along `r`, and the rays they took
+    fn mean(
+        world: &dyn Reflexible,
+        lights: &LightList,
+        settings: &RenderSettings,
+        r: &Ray,
+        seed: u64,
+        samples: u32,
+    ) -> (Vec3, u64) {
+        let camera = Camera::new(
+            &r.origin,
+            &(r.origin + r.direction),
+            &Vec3::new(0.0, 1.0, 0.0),
+            90.0,
+            1.0,
+            0.0,
+            1.0,
+        );
+        let context = Context {
+            world,
+            lights,
+            camera: &camera,
+            settings,
+        };
+        let mut sampler = Sampler::new(seed);
+        let mut rays = 0;
+        let sum = (0..samples).fold(Vec3::new(0.0, 0.0, 0.0), |sum, _| {
+            sum + PathTracer.color(r, &context, &mut rays, &mut sampler)
+        });
+        (sum / f64::from(samples), rays)
+    }
+
+    // a floor through the origin facing up and a spherical light, which the tests below
+    // place and light differently
+    fn floor_and_light(floor: Material, center: Vec3, radius: f64, emit: Vec3) -> ReflexibleList {
+        let list: Vec<Box<dyn Reflexible>> = vec![
+            Box::new(Plane::new(
+                Vec3::new(0.0, 0.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+                floor,
+            )),
+            Box::new(Sphere::new(
+                center,
+                radius,
+                Material::DiffuseLight(DiffuseLight::new(emit)),
+            )),
+        ];
+        ReflexibleList::new(list)
+    }
+
+    // the means of `samples.0` paths sampling the world's lights and of `samples.1`
+    // paths only finding them by chance, both against a black background
+    fn with_and_without_lights(
+        world: &ReflexibleList,
+        r: &Ray,
+        seed: u64,
+        samples: (u32, u32),
+    ) -> (Vec3, Vec3) {
+        let settings = RenderSettings {
+            background: Background::black(),
+            ..RenderSettings::default()
+        };
+        let nothing = ReflexibleList::new(Vec::new());
+        let (sampled, _) = mean(world, &LightList::new(world), &settings, r, seed, samples.0);
+        let (path_traced, _) = mean(
+            world,
+            &LightList::new(&nothing),
+            &settings,
+            r,
+            seed,
+            samples.1,
+        );
+        (sampled, path_traced)
+    }
+
+    fn gray(albedo: f64) -> Material {
+        Material::Lambertian(Lambertian::new(Vec3::new(albedo, albedo, albedo)))
+    }
+
+    #[test]
+    fn emission() {
+        let emit = Vec3::new(4.0, 2.0, 1.0);
+        let world = floor_and_light(
+            Material::Metal(Metal::new(Vec3::new(0.5, 0.5, 0.5), 0.0)),
+            Vec3::new(4.0, 4.0, 0.0),
+            1.5,
+            emit,
+        );
+        let lights = LightList::new(&world);
+        let settings = RenderSettings {
+            background: Background::black(),
+            ..RenderSettings::default()
+        };
+
+        // seen straight on and in the mirror below it
+        let r = Ray::new(Vec3::new(4.0, 8.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        assert_eq!(mean(&world, &lights, &settings, &r, 5, 1).0, emit);
+        let r = Ray::new(Vec3::new(-1.0, 1.0, 0.0), Vec3::new(1.0, -1.0, 0.0));
+        assert_eq!(mean(&world, &lights, &settings, &r, 5, 1).0, 0.5 * emit);
+    }
+
+    #[test]
+    fn emission_adds_to_bounced_light() {
+        // a diffuse floor under a light and a uniform sky, the light covering
+        // sin^2 of the floor's incoming light and the sky the rest
+        let world = floor_and_light(
+            gray(0.5),
+            Vec3::new(0.0, 4.0, 0.0),
+            1.5,
+            Vec3::new(4.0, 4.0, 4.0),
+        );
+        let lights = LightList::new(&world);
+        let settings = RenderSettings {
+            background: Background::Solid(Vec3::new(1.0, 1.0, 1.0)),
+            ..RenderSettings::default()
+        };
+        let covered = 1.5 * 1.5 / 16.0;
+        let expected = 0.5 * (4.0 * covered + (1.0 - covered));
+
+        let r = Ray::new(Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        let (color, _) = mean(&world, &lights, &settings, &r, 7, 20000);
+        assert!((color.x - expected).abs() < 0.02 * expected);
+    }
+
+    #[test]
+    fn light_sampling_agrees_with_path_tracing() {
+        // a floor lit by a sphere straight above it, which gives it a radiance of
+        // albedo * emitted * sin^2 of the angle the sphere covers
+        let world = floor_and_light(
+            gray(0.5),
+            Vec3::new(0.0, 4.0, 0.0),
+            1.5,
+            Vec3::new(4.0, 4.0, 4.0),
+        );
+        let expected = 0.5 * 4.0 * 1.5 * 1.5 / 16.0;
+
+        let r = Ray::new(Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        let (sampled, path_traced) = with_and_without_lights(&world, &r, 11, (1000, 40000));
+        assert!((sampled.x - expected).abs() < 0.01 * expected);
+        assert!((path_traced.x - expected).abs() < 0.05 * expected);
+    }
+
+    #[test]
+    fn planes_are_lit_from_below() {
+        // the floor of the test above, lit and seen from underneath
+        let world = floor_and_light(
+            gray(0.5),
+            Vec3::new(0.0, -4.0, 0.0),
+            1.5,
+            Vec3::new(4.0, 4.0, 4.0),
+        );
+        let expected = 0.5 * 4.0 * 1.5 * 1.5 / 16.0;
+
+        let r = Ray::new(Vec3::new(0.0, -1.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let (sampled, path_traced) = with_and_without_lights(&world, &r, 11, (1000, 40000));
+        assert!((sampled.x - expected).abs() < 0.01 * expected);
+        assert!((path_traced.x - expected).abs() < 0.05 * expected);
+    }
+
+    #[test]
+    fn multiple_importance_sampling_agrees_with_path_tracing() {
+        // a glossy floor reflecting a big light
+        let world = floor_and_light(
+            Material::Metal(Metal::new(Vec3::new(0.8, 0.8, 0.8), 0.5).with_phong_lobe()),
+            Vec3::new(1.0, 3.0, 0.0),
+            2.0,
+            Vec3::new(1.0, 1.0, 1.0),
+        );
+
+        let r = Ray::new(Vec3::new(-2.0, 1.0, 0.0), Vec3::new(1.0, -0.5, 0.0));
+        let (combined, path_traced) = with_and_without_lights(&world, &r, 13, (20000, 80000));
+        assert!((combined.x - path_traced.x).abs() < 0.03 * path_traced.x);
+    }
+
+    #[test]
+    fn russian_roulette_keeps_the_mean() {
+        let list: Vec<Box<dyn Reflexible>> = vec![
+            Box::new(Sphere::new(
+                Vec3::new(0.0, -100.0, -1.0),
+                100.0,
+                Material::Lambertian(Lambertian::new(Vec3::new(0.3, 0.6, 0.7))),
+            )),
+            Box::new(Sphere::new(
+                Vec3::new(-1.0, 1.0, -1.0),
+                1.0,
+                Material::Metal(Metal::new(Vec3::new(0.8, 0.6, 0.2), 0.3)),
+            )),
+            Box::new(Sphere::new(
+                Vec3::new(1.0, 1.0, -1.0),
+                1.0,
+                Material::Dielectric(Dielectric::new(1.5)),
+            )),
+        ];
+        let world = ReflexibleList::new(list);
+        let lights = LightList::new(&world);
+        let r = Ray::new(Vec3::new(0.0, 1.0, 3.0), Vec3::new(0.0, -0.5, -1.0));
+        let render = |min_depth: u32| {
+            let settings = RenderSettings {
+                min_depth,
+                ..RenderSettings::default()
+            };
+            mean(&world, &lights, &settings, &r, 17, 20000)
+        };
+
+        let (all, all_rays) = render(50);
+        let (roulette, roulette_rays) = render(0);
+        assert!((roulette - all).length() < 0.02 * all.length());
+        assert!(roulette_rays < all_rays);
+    }
+}
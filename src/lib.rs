@@ -1,102 +1,477 @@
 extern crate image;
 
+pub mod accumulator;
+pub mod adaptive;
+pub mod aov;
 pub mod camera;
+pub mod checkpoint;
+pub mod error;
+pub mod framebuffer;
+pub mod integrators;
+pub mod lights;
 pub mod materials;
+pub mod obj;
+pub mod output;
+pub mod presets;
+pub mod progress;
 pub mod ray;
 pub mod reflexible;
+pub mod sampler;
+pub mod scene;
+pub mod settings;
+pub mod textures;
+pub mod tile;
+pub mod tonemap;
+pub mod transform;
 pub mod vec3;
 
-use crate::materials::Scatterable;
+use accumulator::{Accumulator, PixelSamples};
+use aov::{Aovs, MaterialIds};
 use camera::Camera;
-use rand::Rng;
+use error::Error;
+use framebuffer::Framebuffer;
+use integrators::{Context, Integrator};
+use lights::LightList;
+use progress::{CancelToken, ConsoleProgress, NoProgress, Progress, ProgressObserver};
 use ray::Ray;
 use reflexible::Reflexible;
-use reflexible::ReflexibleList;
+use sampler::Sampler;
+use settings::{Progressive, RenderSettings};
+use tonemap::{ToneMap, Transfer};
 use vec3::Vec3;
 
+use std::f64::consts::PI;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
 use std::time::Instant;
 
-pub fn ray_tracer(world: &ReflexibleList, cam: &Camera, nx: u32, ny: u32, out_name: &str) {
-    let ns = 100;
+const TILE_SIZE: u32 = 32;
 
-    let mut imgbuf = image::ImageBuffer::new(nx, ny);
+pub fn ray_tracer(
+    world: &dyn Reflexible,
+    cam: &Camera,
+    nx: u32,
+    ny: u32,
+    out_name: &str,
+) -> Result<(), Error> {
+    ray_tracer_with_settings(world, cam, nx, ny, &RenderSettings::default(), out_name)
+}
 
-    let mut rng = rand::thread_rng();
+pub fn ray_tracer_with_settings(
+    world: &dyn Reflexible,
+    cam: &Camera,
+    nx: u32,
+    ny: u32,
+    settings: &RenderSettings,
+    out_name: &str,
+) -> Result<(), Error> {
+    // fail on a bad extension before spending time on the render
+    let format = output::OutputFormat::from_path(Path::new(out_name))?;
 
-    println!("render started and it may take some time.");
+    println!(
+        "render started on {} threads and it may take some time.",
+        settings.threads
+    );
     let now = Instant::now();
 
-    for j in (0..ny).rev() {
-        for i in 0..nx {
-            let mut col = Vec3::new(0.0, 0.0, 0.0);
-            for _ in 0..ns {
-                let ur: f64 = rng.gen();
-                let vr: f64 = rng.gen();
-                let u: f64 = (i as f64 + ur) / nx as f64;
-                let v: f64 = (j as f64 + vr) / ny as f64;
-
-                let r = cam.get_ray(u, v);
-                col = col + color(&r, world, 0);
-            }
-
-            col = col / (ns as f64);
-            col = Vec3::new(f64::sqrt(col.x), f64::sqrt(col.y), f64::sqrt(col.z));
-            let ir = (255.99 * col.r()) as u8;
-            let ig = (255.99 * col.g()) as u8;
-            let ib = (255.99 * col.b()) as u8;
-
-            let pixel = imgbuf.get_pixel_mut(i, ny - (j + 1));
-            *pixel = image::Rgb([ir, ig, ib]);
-            print!(
-                "\rprogress ==> {}/{} = %{}",
-                1 + i + nx * (ny - (j + 1)),
-                nx * ny,
-                (1 + i + nx * (ny - (j + 1))) * 100 / (nx * ny)
-            );
-        }
-    }
+    let console = ConsoleProgress::new();
+    let cancel = CancelToken::new();
+    let writer =
+        output::SnapshotWriter::new(&console, out_name, format, &settings.tone_mapping, &cancel);
+    let (fb, aovs) = render_aovs(world, cam, nx, ny, settings, &writer, &cancel)?;
+    writer.finish()?;
 
     let duration = now.elapsed();
 
-    let _ = image::ImageRgb8(imgbuf).save(out_name);
+    output::save_with_aovs(&fb, &aovs, out_name, format, &settings.tone_mapping)?;
 
     println!(
-        "\n\n'{}' generated to the current path in {} milliseconds! ",
+        "'{}' generated to the current path in {} milliseconds! ",
         out_name,
         duration.as_secs() * 1000 + u64::from(duration.subsec_millis())
     );
+
+    Ok(())
+}
+
+// renders the scene into a linear framebuffer without touching the disk or stdout
+pub fn render(
+    world: &dyn Reflexible,
+    cam: &Camera,
+    nx: u32,
+    ny: u32,
+    settings: &RenderSettings,
+) -> Result<Framebuffer, Error> {
+    render_with(
+        world,
+        cam,
+        nx,
+        ny,
+        settings,
+        &NoProgress,
+        &CancelToken::new(),
+    )
+}
+
+// like `render`, reporting to `observer` after every finished tile. once `cancel` is
+// triggered no new tiles are started and the partial image is returned, with the
+// unfinished tiles left black.
+pub fn render_with(
+    world: &dyn Reflexible,
+    cam: &Camera,
+    nx: u32,
+    ny: u32,
+    settings: &RenderSettings,
+    observer: &dyn ProgressObserver,
+    cancel: &CancelToken,
+) -> Result<Framebuffer, Error> {
+    render_aovs(world, cam, nx, ny, settings, observer, cancel).map(|(fb, _)| fb)
+}
+
+// like `render_with`, also rendering the aovs in `settings.aovs`
+pub fn render_aovs(
+    world: &dyn Reflexible,
+    cam: &Camera,
+    nx: u32,
+    ny: u32,
+    settings: &RenderSettings,
+    observer: &dyn ProgressObserver,
+    cancel: &CancelToken,
+) -> Result<(Framebuffer, Aovs), Error> {
+    validate(nx, ny, settings)?;
+
+    let mut acc = Accumulator::new(nx, ny, settings.aovs);
+    accumulate(world, cam, &mut acc, settings, observer, cancel)?;
+
+    Ok(acc.resolve())
+}
+
+// takes samples into `acc` until every pixel has `settings.samples` of them or has
+// converged. a progressive render goes there one pass at a time, handing snapshots to
+// `observer` as `settings.progressive` asks. samples already in `acc` are kept, so a render
+// can be resumed from a checkpoint.
+pub fn accumulate(
+    world: &dyn Reflexible,
+    cam: &Camera,
+    acc: &mut Accumulator,
+    settings: &RenderSettings,
+    observer: &dyn ProgressObserver,
+    cancel: &CancelToken,
+) -> Result<(), Error> {
+    accumulate_with(
+        world,
+        cam,
+        acc,
+        settings,
+        &settings.integrator,
+        observer,
+        cancel,
+    )
 }
 
-fn color(r: &Ray, world: &Reflexible, depth: i64) -> Vec3 {
-    if let Some(rec) = world.hit(r, 0.001, std::f64::MAX) {
-        let mut scattered: Ray = Ray::new(Vec3::new(1.0, 1.0, 1.0), Vec3::new(1.0, 1.0, 1.0));
-        let mut attenuation: Vec3 = Vec3::new(1.0, 1.0, 1.0);
-
-        if depth < 50
-            && rec
-                .material
-                .scatter(r, &rec, &mut attenuation, &mut scattered)
-        {
-            return attenuation * color(&scattered, world, depth + 1);
-        } else {
-            return Vec3::new(0.0, 0.0, 0.0);
+// like `accumulate`, with `integrator` in place of the one in the settings. checkpoints
+// only record `settings.integrator`, so one taken with another integrator has to be resumed
+// with that same integrator.
+pub fn accumulate_with(
+    world: &dyn Reflexible,
+    cam: &Camera,
+    acc: &mut Accumulator,
+    settings: &RenderSettings,
+    integrator: &dyn Integrator,
+    observer: &dyn ProgressObserver,
+    cancel: &CancelToken,
+) -> Result<(), Error> {
+    let (nx, ny) = (acc.width(), acc.height());
+    validate(nx, ny, settings)?;
+    integrator.validate()?;
+    if acc.aovs() != settings.aovs {
+        return Err(Error::InvalidSettings(
+            "the accumulated aovs don't match the settings".to_string(),
+        ));
+    }
+
+    let job = RenderJob {
+        world,
+        lights: LightList::new(world),
+        materials: MaterialIds::new(world),
+        integrator,
+        cam,
+        settings,
+        nx,
+        ny,
+    };
+    // the sample count every pixel is brought up to in each pass
+    let targets: Vec<u32> = match settings.progressive {
+        Some(_) => (acc.min_samples() + 1..=settings.samples).collect(),
+        None => vec![settings.samples],
+    };
+    let deadline = settings
+        .progressive
+        .and_then(|p| p.time_budget)
+        .map(|budget| Instant::now() + budget);
+    let out_of_time = || deadline.is_some_and(|deadline| Instant::now() >= deadline);
+
+    let tiles = tile::tiles(nx, ny, TILE_SIZE);
+    let tiles_done = AtomicUsize::new(0);
+    let rays_traced = AtomicU64::new(0);
+    let samples_taken = AtomicU64::new(0);
+    let now = Instant::now();
+    let progress = |tiles_done: usize| Progress {
+        tiles_done,
+        tiles_total: tiles.len() * targets.len(),
+        rays: rays_traced.load(Ordering::SeqCst),
+        samples: samples_taken.load(Ordering::SeqCst),
+        elapsed: now.elapsed(),
+    };
+    let mut last_snapshot = now;
+    let last_checkpoint = Mutex::new(now);
+
+    let shared = Mutex::new(&mut *acc);
+    for (pass, &target) in targets.iter().enumerate() {
+        if cancel.is_cancelled() || out_of_time() {
+            break;
         }
-    } else {
-        let unit_direction = Vec3::unit_vector(r.direction);
-        let t: f64 = 0.5 * (unit_direction.y + 1.0);
 
-        (1.0 - t) * Vec3::new(1.0, 1.0, 1.0) + t * Vec3::new(0.5, 0.7, 1.0)
+        let next_tile = AtomicUsize::new(0);
+        let samples_before = samples_taken.load(Ordering::SeqCst);
+
+        thread::scope(|s| {
+            for _ in 0..settings.threads {
+                s.spawn(|| {
+                    let mut pixels = Vec::with_capacity((TILE_SIZE * TILE_SIZE) as usize);
+
+                    'tiles: loop {
+                        if cancel.is_cancelled() || out_of_time() {
+                            break;
+                        }
+                        let index = next_tile.fetch_add(1, Ordering::SeqCst);
+                        let tile = match tiles.get(index) {
+                            Some(tile) => tile,
+                            None => break,
+                        };
+
+                        shared.lock().unwrap().read_tile(tile, &mut pixels);
+
+                        let mut rays = 0;
+                        let mut samples = 0;
+                        let mut pixel = pixels.iter_mut();
+                        for y in tile.y0..tile.y1 {
+                            // a cancelled tile is dropped, its pixels keep what they had
+                            if cancel.is_cancelled() {
+                                break 'tiles;
+                            }
+                            for x in tile.x0..tile.x1 {
+                                let pixel = pixel.next().unwrap();
+                                let before = pixel.stats.count();
+                                job.sample_pixel(x, y, target, pixel, &mut rays);
+                                samples += u64::from(pixel.stats.count() - before);
+                            }
+                        }
+
+                        let mut acc = shared.lock().unwrap();
+                        acc.write_tile(tile, &pixels);
+
+                        rays_traced.fetch_add(rays, Ordering::SeqCst);
+                        samples_taken.fetch_add(samples, Ordering::SeqCst);
+                        // reported while holding the lock so observers see ordered updates
+                        let progress = progress(tiles_done.fetch_add(1, Ordering::SeqCst) + 1);
+                        observer.on_progress(&progress);
+
+                        // copied under the lock and saved after it, so the other threads
+                        // don't wait on the disk. `last_checkpoint` stays locked meanwhile,
+                        // which keeps a slow save from overlapping the next one
+                        let checkpoint = match settings.checkpoint_interval {
+                            Some(interval) => match last_checkpoint.try_lock() {
+                                Ok(last) if last.elapsed() >= interval => Some((last, acc.clone())),
+                                _ => None,
+                            },
+                            None => None,
+                        };
+                        drop(acc);
+                        if let Some((mut last, copy)) = checkpoint {
+                            observer.on_checkpoint(&copy, &progress);
+                            *last = Instant::now();
+                        }
+                    }
+                });
+            }
+        });
+
+        // every pixel has converged
+        if samples_taken.load(Ordering::SeqCst) == samples_before {
+            break;
+        }
+
+        if let Some(progressive) = settings.progressive {
+            let passes = pass as u32 + 1;
+            let by_passes = progressive
+                .snapshot_passes
+                .is_some_and(|n| passes.is_multiple_of(n));
+            let by_time = progressive
+                .snapshot_interval
+                .is_some_and(|interval| last_snapshot.elapsed() >= interval);
+
+            if (by_passes || by_time) && passes < targets.len() as u32 {
+                let (fb, aovs) = shared.lock().unwrap().resolve();
+                observer.on_snapshot(&fb, &aovs, &progress(tiles_done.load(Ordering::SeqCst)));
+                last_snapshot = Instant::now();
+            }
+        }
     }
+
+    let progress = progress(tiles_done.load(Ordering::SeqCst));
+    if settings.checkpoint_interval.is_some() {
+        observer.on_checkpoint(&shared.lock().unwrap(), &progress);
+    }
+    observer.on_finish(&progress, cancel.is_cancelled());
+
+    Ok(())
 }
 
-pub fn random_in_unit_sphere() -> Vec3 {
+// what every pixel of a render needs
+struct RenderJob<'a, 'w> {
+    world: &'w dyn Reflexible,
+    lights: LightList<'w>,
+    materials: MaterialIds,
+    integrator: &'a dyn Integrator,
+    cam: &'a Camera,
+    settings: &'a RenderSettings,
+    nx: u32,
+    ny: u32,
+}
+
+impl<'a, 'w> RenderJob<'a, 'w> {
+    // takes the samples of pixel (i, y) up to `target`, fewer if it converges first
+    fn sample_pixel(&self, i: u32, y: u32, target: u32, pixel: &mut PixelSamples, rays: &mut u64) {
+        let settings = self.settings;
+        let context = Context {
+            world: self.world,
+            lights: &self.lights,
+            camera: self.cam,
+            settings,
+        };
+
+        for sample in pixel.stats.count()..target {
+            if let Some(ref adaptive) = settings.adaptive {
+                if adaptive.converged(&pixel.stats) {
+                    break;
+                }
+            }
+
+            let (r, mut sampler) = self.camera_ray(i, y, sample);
+            if !settings.aovs.is_empty() {
+                if let Some(rec) = self.world.hit(&r, settings.t_min, f64::MAX) {
+                    for aov in settings.aovs.iter() {
+                        if !aov.is_id() || sample == 0 {
+                            pixel.aov_sums[aov as usize] =
+                                pixel.aov_sums[aov as usize] + aov.value(&r, &rec, &self.materials);
+                        }
+                    }
+                }
+            }
+
+            let col = self.integrator.color(&r, &context, rays, &mut sampler);
+            pixel.sum = pixel.sum + col;
+            pixel.stats.add(col);
+        }
+    }
+
+    // the camera ray of a sample, and the sampler to carry on with
+    fn camera_ray(&self, i: u32, y: u32, sample: u32) -> (Ray, Sampler) {
+        // image rows go top to bottom while v goes bottom to top
+        let j = self.ny - (y + 1);
+
+        let mut sampler = Sampler::for_sample(self.settings.seed, i, y, sample);
+        let ur: f64 = sampler.next_f64();
+        let vr: f64 = sampler.next_f64();
+        let u: f64 = (i as f64 + ur) / self.nx as f64;
+        let v: f64 = (j as f64 + vr) / self.ny as f64;
+
+        (self.cam.get_ray(u, v, &mut sampler), sampler)
+    }
+}
+
+fn validate(nx: u32, ny: u32, settings: &RenderSettings) -> Result<(), Error> {
+    if nx == 0 || ny == 0 {
+        return Err(Error::InvalidSettings(format!(
+            "image size {}x{} is empty",
+            nx, ny
+        )));
+    }
+    if settings.samples == 0 {
+        return Err(Error::InvalidSettings(
+            "samples per pixel must be at least 1".to_string(),
+        ));
+    }
+    if settings.threads == 0 {
+        return Err(Error::InvalidSettings(
+            "thread count must be at least 1".to_string(),
+        ));
+    }
+    if let Some(adaptive) = settings.adaptive {
+        if adaptive.threshold.is_nan() || adaptive.threshold <= 0.0 {
+            return Err(Error::InvalidSettings(format!(
+                "noise threshold must be positive, got {}",
+                adaptive.threshold
+            )));
+        }
+        if adaptive.min_samples == 0 || adaptive.min_samples > settings.samples {
+            return Err(Error::InvalidSettings(format!(
+                "minimum samples must be between 1 and the {} samples per pixel, got {}",
+                settings.samples, adaptive.min_samples
+            )));
+        }
+    }
+    if let Some(Progressive {
+        snapshot_passes: Some(0),
+        ..
+    }) = settings.progressive
+    {
+        return Err(Error::InvalidSettings(
+            "snapshots need at least one pass between them".to_string(),
+        ));
+    }
+    if let Transfer::Gamma(gamma) = settings.tone_mapping.transfer {
+        if gamma.is_nan() || gamma <= 0.0 {
+            return Err(Error::InvalidSettings(format!(
+                "gamma must be positive, got {}",
+                gamma
+            )));
+        }
+    }
+    if let ToneMap::ReinhardExtended { white } = settings.tone_mapping.tone_map {
+        if white.is_nan() || white <= 0.0 {
+            return Err(Error::InvalidSettings(format!(
+                "white point must be positive, got {}",
+                white
+            )));
+        }
+    }
+    if !settings.tone_mapping.exposure.is_finite() {
+        return Err(Error::InvalidSettings(format!(
+            "exposure must be finite, got {}",
+            settings.tone_mapping.exposure
+        )));
+    }
+
+    Ok(())
+}
+
+pub fn default_threads() -> usize {
+    thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+}
+
+pub fn random_in_unit_sphere(sampler: &mut Sampler) -> Vec3 {
     let mut p: Vec3;
-    let mut rng = rand::thread_rng();
 
     loop {
-        let x: f64 = rng.gen();
-        let y: f64 = rng.gen();
-        let z: f64 = rng.gen();
+        let x: f64 = sampler.next_f64();
+        let y: f64 = sampler.next_f64();
+        let z: f64 = sampler.next_f64();
         p = 2.0 * Vec3::new(x, y, z) - Vec3::new(0.0, 0.0, 0.0);
 
         if p.squared_length() >= 1.0 {
@@ -105,3 +480,418 @@ pub fn random_in_unit_sphere() -> Vec3 {
     }
     p
 }
+
+// uniform over the unit sphere
+pub fn random_unit_vector(sampler: &mut Sampler) -> Vec3 {
+    let z = 1.0 - 2.0 * sampler.next_f64();
+    let r = f64::sqrt(1.0 - z * z);
+    let phi = 2.0 * PI * sampler.next_f64();
+
+    Vec3::new(r * f64::cos(phi), r * f64::sin(phi), z)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::Material;
+    use crate::materials::{dielectric::Dielectric, lambertian::Lambertian, metal::Metal};
+    use crate::reflexible::sphere::Sphere;
+    use crate::reflexible::ReflexibleList;
+    use crate::settings::Background;
+    use crate::tonemap::ToneMapping;
+
+    fn test_scene() -> (ReflexibleList, Camera) {
+        let list: Vec<Box<dyn Reflexible>> = vec![
+            Box::new(Sphere::new(
+                Vec3::new(0.0, -100.0, -1.0),
+                100.0,
+                Material::Lambertian(Lambertian::new(Vec3::new(0.3, 0.6, 0.7))),
+            )),
+            Box::new(Sphere::new(
+                Vec3::new(-1.0, 1.0, -1.0),
+                1.0,
+                Material::Metal(Metal::new(Vec3::new(0.8, 0.6, 0.2), 0.3)),
+            )),
+            Box::new(Sphere::new(
+                Vec3::new(1.0, 1.0, -1.0),
+                1.0,
+                Material::Dielectric(Dielectric::new(1.5)),
+            )),
+        ];
+        let cam = Camera::new(
+            &Vec3::new(0.0, 2.0, 6.0),
+            &Vec3::new(0.0, 1.0, -1.0),
+            &Vec3::new(0.0, 1.0, 0.0),
+            40.0,
+            2.0,
+            0.1,
+            7.0,
+        );
+
+        (ReflexibleList::new(list), cam)
+    }
+
+    #[test]
+    fn seeded_render_is_reproducible() {
+        let (world, cam) = test_scene();
+        let settings = RenderSettings {
+            samples: 4,
+            threads: 1,
+            seed: 11,
+            ..Default::default()
+        };
+
+        let single = render(&world, &cam, 40, 20, &settings).unwrap();
+        let threaded = render(
+            &world,
+            &cam,
+            40,
+            20,
+            &RenderSettings {
+                threads: 3,
+                ..settings
+            },
+        )
+        .unwrap();
+        let reseeded = render(
+            &world,
+            &cam,
+            40,
+            20,
+            &RenderSettings {
+                seed: 12,
+                ..settings
+            },
+        )
+        .unwrap();
+
+        assert_eq!(single, threaded);
+        assert_ne!(single, reseeded);
+    }
+
+    #[test]
+    fn settings_change_the_image() {
+        let (world, cam) = test_scene();
+        let settings = RenderSettings {
+            samples: 4,
+            threads: 1,
+            ..Default::default()
+        };
+        let fb = render(&world, &cam, 40, 20, &settings).unwrap();
+
+        // the top left corner looks past the spheres
+        let black = render(
+            &world,
+            &cam,
+            40,
+            20,
+            &RenderSettings {
+                background: Background::Solid(Vec3::new(0.0, 0.0, 0.0)),
+                ..settings
+            },
+        )
+        .unwrap();
+        assert!(fb.get(0, 0).length() > 0.5);
+        assert_eq!(black.get(0, 0), Vec3::new(0.0, 0.0, 0.0));
+
+        // cutting the bounces short loses the light they would have carried
+        let shallow = render(
+            &world,
+            &cam,
+            40,
+            20,
+            &RenderSettings {
+                max_depth: 1,
+                ..settings
+            },
+        )
+        .unwrap();
+        let brightness = |fb: &Framebuffer| fb.pixels().iter().map(|p| p.length()).sum::<f64>();
+        assert!(brightness(&shallow) < brightness(&fb));
+
+        let gamma = |g| {
+            fb.to_rgb8(&ToneMapping {
+                transfer: Transfer::Gamma(g),
+                ..Default::default()
+            })
+        };
+        assert_ne!(gamma(1.0).into_raw(), gamma(2.2).into_raw());
+        assert!(gamma(1.0).get_pixel(0, 0)[0] < gamma(2.2).get_pixel(0, 0)[0]);
+    }
+
+    #[test]
+    fn validates_the_integrator_rendered() {
+        let (world, cam) = test_scene();
+        let settings = RenderSettings {
+            samples: 1,
+            threads: 1,
+            ..Default::default()
+        };
+        let ao = integrators::ambient_occlusion::AmbientOcclusion::new();
+        let mut acc = Accumulator::new(8, 4, settings.aovs);
+        let mut accumulate_ao = |ao| {
+            accumulate_with(
+                &world,
+                &cam,
+                &mut acc,
+                &settings,
+                &ao,
+                &progress::NoProgress,
+                &CancelToken::new(),
+            )
+        };
+
+        assert!(accumulate_ao(ao).is_ok());
+        match accumulate_ao(ao.with_distance(0.0)) {
+            Err(Error::InvalidSettings(_)) => (),
+            r => panic!("unexpected {:?}", r.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn aovs() {
+        let (world, cam) = test_scene();
+        let settings = RenderSettings {
+            samples: 4,
+            threads: 2,
+            aovs: aov::Aov::ALL
+                .iter()
+                .fold(aov::AovSet::new(), |set, aov| set.with(*aov)),
+            ..Default::default()
+        };
+
+        let (fb, aovs) = render_aovs(
+            &world,
+            &cam,
+            40,
+            20,
+            &settings,
+            &NoProgress,
+            &CancelToken::new(),
+        )
+        .unwrap();
+        // the extra hits don't disturb the samples of the image
+        assert_eq!(
+            fb,
+            render(
+                &world,
+                &cam,
+                40,
+                20,
+                &RenderSettings {
+                    aovs: aov::AovSet::new(),
+                    ..settings
+                }
+            )
+            .unwrap()
+        );
+
+        let get = |aov, x, y| aovs.get(aov).unwrap().get(x, y);
+        // top left is sky, the bottom row is ground, the middle has the metal sphere
+        assert_eq!(get(aov::Aov::Depth, 0, 0), Vec3::new(0.0, 0.0, 0.0));
+        assert_eq!(get(aov::Aov::ObjectId, 0, 0).x, 0.0);
+        assert_eq!(get(aov::Aov::ObjectId, 20, 19).x, 1.0);
+        assert!(get(aov::Aov::Depth, 20, 19).x > 0.0);
+        assert!(get(aov::Aov::Normal, 20, 19).y > 0.9);
+
+        let metal = (0..40)
+            .find(|x| get(aov::Aov::ObjectId, *x, 10).x == 2.0)
+            .unwrap();
+        let ground = get(aov::Aov::MaterialId, 20, 19).x;
+        assert!(ground > 0.0);
+        assert_eq!(get(aov::Aov::MaterialId, 0, 19).x, ground);
+        assert!(get(aov::Aov::MaterialId, metal, 10).x > 0.0);
+        assert!(get(aov::Aov::MaterialId, metal, 10).x != ground);
+        assert_eq!(get(aov::Aov::Albedo, metal, 10), Vec3::new(0.8, 0.6, 0.2));
+    }
+
+    #[test]
+    fn adaptive_sampling() {
+        let (world, cam) = test_scene();
+        let settings = RenderSettings {
+            samples: 64,
+            adaptive: Some(adaptive::AdaptiveSampling::new(0.02)),
+            threads: 1,
+            aovs: aov::AovSet::new().with(aov::Aov::SampleCount),
+            ..Default::default()
+        };
+
+        let (fb, aovs) = render_aovs(
+            &world,
+            &cam,
+            40,
+            20,
+            &settings,
+            &NoProgress,
+            &CancelToken::new(),
+        )
+        .unwrap();
+        let counts = aovs.get(aov::Aov::SampleCount).unwrap();
+
+        // flat sky stops at the minimum, the noisy sphere edges go on
+        assert_eq!(counts.get(0, 0).x, 16.0);
+        assert!(counts.pixels().iter().any(|p| p.x > 16.0));
+        assert!(counts.pixels().iter().all(|p| p.x >= 16.0 && p.x <= 64.0));
+
+        let threaded = render(
+            &world,
+            &cam,
+            40,
+            20,
+            &RenderSettings {
+                threads: 3,
+                ..settings
+            },
+        )
+        .unwrap();
+        assert_eq!(fb, threaded);
+    }
+
+    #[test]
+    fn progressive_matches_a_single_pass() {
+        struct Snapshots(Mutex<Vec<u64>>);
+
+        impl ProgressObserver for Snapshots {
+            fn on_progress(&self, _progress: &Progress) {}
+
+            fn on_snapshot(&self, _fb: &Framebuffer, _aovs: &Aovs, progress: &Progress) {
+                self.0.lock().unwrap().push(progress.samples);
+            }
+        }
+
+        let (world, cam) = test_scene();
+        let settings = RenderSettings {
+            samples: 5,
+            threads: 2,
+            ..Default::default()
+        };
+        let snapshots = Snapshots(Mutex::new(Vec::new()));
+
+        let progressive = render_with(
+            &world,
+            &cam,
+            40,
+            20,
+            &RenderSettings {
+                progressive: Some(Progressive {
+                    snapshot_passes: Some(2),
+                    ..Default::default()
+                }),
+                ..settings
+            },
+            &snapshots,
+            &CancelToken::new(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            progressive,
+            render(&world, &cam, 40, 20, &settings).unwrap()
+        );
+        // after passes 2 and 4, the last pass is the result itself
+        assert_eq!(*snapshots.0.lock().unwrap(), vec![2 * 800, 4 * 800]);
+    }
+
+    #[test]
+    fn cancelling_stops_the_render() {
+        // cancels after the first tile, and keeps what `on_finish` was told
+        struct Canceller(CancelToken, Mutex<Option<(Progress, bool)>>);
+
+        impl ProgressObserver for Canceller {
+            fn on_progress(&self, _progress: &Progress) {
+                self.0.cancel();
+            }
+
+            fn on_finish(&self, progress: &Progress, cancelled: bool) {
+                *self.1.lock().unwrap() = Some((*progress, cancelled));
+            }
+        }
+
+        let (world, cam) = test_scene();
+        let settings = RenderSettings {
+            samples: 5,
+            threads: 2,
+            ..Default::default()
+        };
+        let canceller = Canceller(CancelToken::new(), Mutex::new(None));
+        let mut acc = Accumulator::new(40, 20, settings.aovs);
+        accumulate(&world, &cam, &mut acc, &settings, &canceller, &canceller.0).unwrap();
+
+        assert!(acc.total_samples() < 40 * 20 * 5);
+        let (progress, cancelled) = canceller.1.lock().unwrap().unwrap();
+        assert!(cancelled);
+        assert!(progress.tiles_done < progress.tiles_total);
+        assert_eq!(progress.samples, acc.total_samples());
+    }
+
+    #[test]
+    fn resumed_render_matches_an_uninterrupted_one() {
+        // keeps the last checkpoint
+        struct Checkpoints(Mutex<Vec<u8>>, RenderSettings);
+
+        impl ProgressObserver for Checkpoints {
+            fn on_progress(&self, _progress: &Progress) {}
+
+            fn on_checkpoint(&self, acc: &Accumulator, _progress: &Progress) {
+                let mut bytes = self.0.lock().unwrap();
+                bytes.clear();
+                checkpoint::write(&mut *bytes, acc, &self.1, 0).unwrap();
+            }
+        }
+
+        let (world, cam) = test_scene();
+        let settings = RenderSettings {
+            samples: 24,
+            adaptive: Some(adaptive::AdaptiveSampling {
+                threshold: 0.05,
+                min_samples: 8,
+            }),
+            threads: 2,
+            aovs: aov::Aov::ALL
+                .iter()
+                .fold(aov::AovSet::new(), |set, aov| set.with(*aov)),
+            checkpoint_interval: Some(std::time::Duration::from_secs(0)),
+            ..Default::default()
+        };
+        let checkpoints = Checkpoints(Mutex::new(Vec::new()), settings);
+
+        let mut acc = Accumulator::new(40, 20, settings.aovs);
+        accumulate(
+            &world,
+            &cam,
+            &mut acc,
+            &RenderSettings {
+                samples: 12,
+                ..settings
+            },
+            &checkpoints,
+            &CancelToken::new(),
+        )
+        .unwrap();
+
+        let bytes = checkpoints.0.lock().unwrap().clone();
+        let mut resumed = checkpoint::read(&mut &bytes[..], 40, 20, &settings, 0).unwrap();
+        assert_eq!(resumed.total_samples(), acc.total_samples());
+        accumulate(
+            &world,
+            &cam,
+            &mut resumed,
+            &settings,
+            &NoProgress,
+            &CancelToken::new(),
+        )
+        .unwrap();
+
+        let uninterrupted = render_aovs(
+            &world,
+            &cam,
+            40,
+            20,
+            &settings,
+            &NoProgress,
+            &CancelToken::new(),
+        )
+        .unwrap();
+        assert_eq!(resumed.resolve(), uninterrupted);
+    }
+}
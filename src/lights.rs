@@ -0,0 +1,132 @@
+use crate::materials::Material;
+use crate::ray::Ray;
+use crate::reflexible::Reflexible;
+use crate::sampler::Sampler;
+use crate::vec3::Vec3;
+
+use std::ptr;
+
+// an emissive object that can be sampled directly, see `Reflexible::lights`. densities are
+// per unit solid angle as seen from `origin`.
+pub trait Light: Send + Sync {
+    // a direction from `origin` toward a random point of the light and its density, None
+    // if the light can't be seen from there
+    fn sample(&self, origin: Vec3, sampler: &mut Sampler) -> Option<(Vec3, f64)>;
+
+    // the density of `sample` picking `direction`, 0 if it misses the light
+    fn pdf(&self, origin: Vec3, direction: Vec3) -> f64;
+
+    fn material(&self) -> &Material;
+}
+
+impl<T: Light + ?Sized> Light for &T {
+    fn sample(&self, origin: Vec3, sampler: &mut Sampler) -> Option<(Vec3, f64)> {
+        (**self).sample(origin, sampler)
+    }
+
+    fn pdf(&self, origin: Vec3, direction: Vec3) -> f64 {
+        (**self).pdf(origin, direction)
+    }
+
+    fn material(&self) -> &Material {
+        (**self).material()
+    }
+}
+
+// the lights of a world, picked uniformly
+pub struct LightList<'w> {
+    lights: Vec<Box<dyn Light + 'w>>,
+}
+
+impl<'w> LightList<'w> {
+    pub fn new(world: &'w dyn Reflexible) -> LightList<'w> {
+        let mut lights = Vec::new();
+        world.lights(&mut lights);
+        LightList { lights }
+    }
+
+    pub fn len(&self) -> usize {
+        self.lights.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lights.is_empty()
+    }
+
+    // a random light and the probability it had of being picked
+    pub fn pick(&self, sampler: &mut Sampler) -> Option<(&dyn Light, f64)> {
+        if self.lights.is_empty() {
+            return None;
+        }
+        let n = self.lights.len();
+        let index = ((sampler.next_f64() * n as f64) as usize).min(n - 1);
+        Some((&*self.lights[index], 1.0 / n as f64))
+    }
+
+    // the light `r` hit, on a surface with `material`. lights don't share materials, so
+    // that is enough to tell them apart, but instances of one object share it and only
+    // the ones `r` points at count.
+    pub fn find(&self, material: &Material, r: &Ray) -> Option<&dyn Light> {
+        self.lights
+            .iter()
+            .find(|light| {
+                ptr::eq(light.material(), material) && light.pdf(r.origin, r.direction) > 0.0
+            })
+            .map(|light| &**light)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::diffuse_light::DiffuseLight;
+    use crate::materials::lambertian::Lambertian;
+    use crate::reflexible::box_shape::BoxShape;
+    use crate::reflexible::bvh::Bvh;
+    use crate::reflexible::rect::XzRect;
+    use crate::reflexible::sphere::Sphere;
+
+    #[test]
+    fn lights() {
+        let light = || Material::DiffuseLight(DiffuseLight::new(Vec3::new(4.0, 4.0, 4.0)));
+        let list: Vec<Box<dyn Reflexible>> = vec![
+            Box::new(Sphere::new(
+                Vec3::new(0.0, 0.0, 0.0),
+                1.0,
+                Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+            )),
+            Box::new(Sphere::new(Vec3::new(0.0, 5.0, 0.0), 0.5, light())),
+            Box::new(XzRect::new(-1.0, 1.0, -1.0, 1.0, 8.0, light()).flipped()),
+            Box::new(BoxShape::new(
+                Vec3::new(3.0, 0.0, 0.0),
+                Vec3::new(4.0, 1.0, 1.0),
+                light(),
+            )),
+        ];
+        let world = Bvh::new(list);
+        let lights = LightList::new(&world);
+        // the box is six rectangles
+        assert_eq!(lights.len(), 8);
+
+        let mut sampler = Sampler::new(7);
+        let (light, probability) = lights.pick(&mut sampler).unwrap();
+        assert_eq!(probability, 1.0 / 8.0);
+        let origin = Vec3::new(1.0, -3.0, 2.0);
+        let (direction, _) = light.sample(origin, &mut sampler).unwrap();
+        let r = Ray::new(origin, direction);
+        assert!(lights.find(light.material(), &r).is_some());
+        let away = Ray::new(origin, -direction);
+        assert!(lights.find(light.material(), &away).is_none());
+        let other = light.material().clone();
+        assert!(lights.find(&other, &r).is_none());
+
+        let dark = Sphere::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            1.0,
+            Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+        );
+        let lights = LightList::new(&dark);
+        assert!(lights.is_empty());
+        assert!(lights.pick(&mut sampler).is_none());
+    }
+}
@@ -0,0 +1,35 @@
+use super::Scatterable;
+use crate::ray::Ray;
+use crate::reflexible::HitRecord;
+use crate::sampler::Sampler;
+use crate::vec3::Vec3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DiffuseLight {
+    emit: Vec3,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Vec3) -> DiffuseLight {
+        DiffuseLight { emit }
+    }
+}
+
+impl Scatterable for DiffuseLight {
+    fn scatter(
+        &self,
+        _r_in: &Ray,
+        _rec: &HitRecord,
+        _sampler: &mut Sampler,
+    ) -> Option<(Vec3, Ray)> {
+        None
+    }
+
+    fn emitted(&self, _rec: &HitRecord) -> Vec3 {
+        self.emit
+    }
+
+    fn albedo(&self, _rec: &HitRecord) -> Vec3 {
+        self.emit
+    }
+}
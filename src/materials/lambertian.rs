@@ -1,31 +1,55 @@
 use super::Scatterable;
-use crate::random_in_unit_sphere;
+use crate::random_unit_vector;
 use crate::ray::Ray;
 use crate::reflexible::HitRecord;
+use crate::sampler::Sampler;
+use crate::textures::{Texturable, Texture};
 use crate::vec3::Vec3;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+use std::f64::consts::PI;
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Lambertian {
-    albedo: Vec3,
+    albedo: Texture,
 }
 
 impl Lambertian {
     pub fn new(albedo: Vec3) -> Lambertian {
+        Lambertian::textured(Texture::solid(albedo))
+    }
+
+    pub fn textured(albedo: Texture) -> Lambertian {
         Lambertian { albedo }
     }
 }
 
 impl Scatterable for Lambertian {
-    fn scatter(
-        &self,
-        _r_in: &Ray,
-        rec: &HitRecord,
-        attenuation: &mut Vec3,
-        scattered: &mut Ray,
-    ) -> bool {
-        let target = rec.p + rec.normal + random_in_unit_sphere();
-        *scattered = Ray::new(rec.p, target - rec.p);
-        *attenuation = self.albedo.clone();
-        true
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, sampler: &mut Sampler) -> Option<(Vec3, Ray)> {
+        // cosine weighted around the normal, which the albedo alone makes up for
+        let mut direction = rec.normal + random_unit_vector(sampler);
+        if direction.squared_length() < 1e-12 {
+            direction = rec.normal;
+        }
+        Some((
+            self.albedo.value(rec.u, rec.v, &rec.p),
+            Ray::new(rec.p, direction).with_time(r_in.time),
+        ))
+    }
+
+    fn albedo(&self, rec: &HitRecord) -> Vec3 {
+        self.albedo.value(rec.u, rec.v, &rec.p)
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, direction: Vec3) -> Vec3 {
+        self.albedo(rec) * self.pdf(r_in, rec, direction)
+    }
+
+    fn pdf(&self, _r_in: &Ray, rec: &HitRecord, direction: Vec3) -> f64 {
+        let cosine = rec.normal.dot(&Vec3::unit_vector(direction));
+        cosine.max(0.0) / PI
+    }
+
+    fn is_specular(&self) -> bool {
+        false
     }
 }
@@ -2,37 +2,172 @@ use super::Scatterable;
 use crate::random_in_unit_sphere;
 use crate::ray::Ray;
 use crate::reflexible::HitRecord;
+use crate::sampler::Sampler;
+use crate::textures::{Texturable, Texture};
 use crate::vec3::Vec3;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+use std::f64::consts::PI;
+
+// phong lobes narrower than this are mirrors, their exponent would overflow
+const MIN_FUZZ: f64 = 1e-3;
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Metal {
-    albedo: Vec3,
+    albedo: Texture,
     fuzz: f64,
+    phong: bool,
 }
 
 impl Metal {
     pub fn new(albedo: Vec3, f: f64) -> Metal {
-        let fuzz;
-        if f < 1.0 {
-            fuzz = f;
-        } else {
-            fuzz = 1.0;
+        Metal::textured(Texture::solid(albedo), f)
+    }
+
+    pub fn textured(albedo: Texture, f: f64) -> Metal {
+        // negative and nan fuzz make a mirror
+        let fuzz = if f > 0.0 { f.min(1.0) } else { 0.0 };
+        Metal {
+            albedo,
+            fuzz,
+            phong: false,
         }
-        Metal { albedo, fuzz }
+    }
+
+    // by default fuzz jitters the mirror direction inside a sphere of radius fuzz, which
+    // has no density the integrator could use. a phong lobe can be weighted against
+    // light samples, at the cost of a different look.
+    pub fn with_phong_lobe(mut self) -> Metal {
+        self.phong = true;
+        self
+    }
+
+    // the phong lobe's exponent, the inverse of how .mtl exponents are turned into fuzz
+    fn exponent(&self) -> f64 {
+        2.0 / (self.fuzz * self.fuzz) - 2.0
+    }
+
+    fn reflected(r_in: &Ray, rec: &HitRecord) -> Vec3 {
+        super::reflect(Vec3::unit_vector(r_in.direction), rec.normal)
     }
 }
 
 impl Scatterable for Metal {
-    fn scatter(
-        &self,
-        r_in: &Ray,
-        rec: &HitRecord,
-        attenuation: &mut Vec3,
-        scattered: &mut Ray,
-    ) -> bool {
-        let reflected = super::reflect(Vec3::unit_vector(r_in.direction), rec.normal);
-        *scattered = Ray::new(rec.p, reflected + self.fuzz * random_in_unit_sphere());
-        *attenuation = self.albedo.clone();
-        Vec3::dot(&scattered.direction, &rec.normal) > 0.0
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, sampler: &mut Sampler) -> Option<(Vec3, Ray)> {
+        let reflected = Metal::reflected(r_in, rec);
+        let direction = if !self.phong {
+            reflected + self.fuzz * random_in_unit_sphere(sampler)
+        } else if self.is_specular() {
+            reflected
+        } else {
+            let (u, v, w) = Vec3::basis(reflected);
+            let cosine = sampler.next_f64().powf(1.0 / (self.exponent() + 1.0));
+            let sine = f64::sqrt((1.0 - cosine * cosine).max(0.0));
+            let phi = 2.0 * PI * sampler.next_f64();
+            f64::cos(phi) * sine * u + f64::sin(phi) * sine * v + cosine * w
+        };
+        // the lobe reaching under the surface is absorbed
+        if Vec3::dot(&direction, &rec.normal) > 0.0 {
+            Some((
+                self.albedo.value(rec.u, rec.v, &rec.p),
+                Ray::new(rec.p, direction).with_time(r_in.time),
+            ))
+        } else {
+            None
+        }
+    }
+
+    fn albedo(&self, rec: &HitRecord) -> Vec3 {
+        self.albedo.value(rec.u, rec.v, &rec.p)
+    }
+
+    // the lobe already has the cosine in it, so that scattering keeps the albedo
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, direction: Vec3) -> Vec3 {
+        self.albedo(rec) * self.pdf(r_in, rec, direction)
+    }
+
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, direction: Vec3) -> f64 {
+        if self.is_specular() || direction.dot(&rec.normal) <= 0.0 {
+            return 0.0;
+        }
+        let n = self.exponent();
+        let cosine =
+            Vec3::unit_vector(Metal::reflected(r_in, rec)).dot(&Vec3::unit_vector(direction));
+        if cosine > 0.0 {
+            (n + 1.0) / (2.0 * PI) * cosine.powf(n)
+        } else {
+            0.0
+        }
+    }
+
+    // jittered reflections are left to the bounced rays, like mirrors
+    fn is_specular(&self) -> bool {
+        !self.phong || self.fuzz < MIN_FUZZ
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::Material;
+    use crate::obj::MtlMaterial;
+
+    #[test]
+    fn fuzz() {
+        let metal = |fuzz| Metal::new(Vec3::new(0.8, 0.8, 0.8), fuzz);
+        assert_eq!(metal(-0.5).fuzz, 0.0);
+        assert_eq!(metal(f64::NAN).fuzz, 0.0);
+        assert_eq!(metal(2.0).fuzz, 1.0);
+
+        // only phong lobes have a density, and only wide enough ones
+        assert!(metal(0.5).is_specular());
+        assert!(!metal(0.5).with_phong_lobe().is_specular());
+        assert!(metal(1e-200).with_phong_lobe().is_specular());
+        assert!(!metal(MIN_FUZZ).with_phong_lobe().is_specular());
+
+        // the narrowest lobe still has a finite density everywhere
+        let tight = metal(MIN_FUZZ).with_phong_lobe();
+        let material = Material::Metal(tight.clone());
+        let rec = HitRecord::new(
+            1.0,
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            &material,
+        );
+        let r_in = Ray::new(Vec3::new(-1.0, 1.0, 0.0), Vec3::new(1.0, -1.0, 0.0));
+        for direction in &[
+            Vec3::new(1.0, 1.0, 0.0),
+            Vec3::new(1.0, 1.001, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+        ] {
+            let pdf = tight.pdf(&r_in, &rec, *direction);
+            assert!(pdf.is_finite() && pdf >= 0.0, "{}", pdf);
+        }
+        assert!(tight.pdf(&r_in, &rec, Vec3::new(1.0, 1.0, 0.0)) > 0.0);
+    }
+
+    #[test]
+    fn exponent() {
+        let exponent = |fuzz| Metal::new(Vec3::new(0.8, 0.8, 0.8), fuzz).exponent();
+        assert_eq!(exponent(1.0), 0.0);
+        assert_eq!(exponent(0.5), 6.0);
+        assert!((exponent(0.1) - 198.0).abs() < 1e-9);
+        // fuzz is capped at 1
+        assert_eq!(exponent(2.0), 0.0);
+
+        // .mtl exponents come back out
+        for &ns in &[0.0, 10.0, 96.0, 500.0] {
+            let mtl = MtlMaterial {
+                ks: Vec3::new(0.9, 0.9, 0.9),
+                ns,
+                illum: 3,
+                ..MtlMaterial::default()
+            };
+            match mtl.to_material() {
+                Material::Metal(metal) => {
+                    assert!((metal.exponent() - ns).abs() < 1e-9 * (1.0 + ns))
+                }
+                _ => panic!("not a metal"),
+            }
+        }
     }
 }
@@ -1,40 +1,110 @@
 use crate::ray::Ray;
 use crate::reflexible::HitRecord;
+use crate::sampler::Sampler;
 use crate::vec3::Vec3;
 
 pub mod dielectric;
+pub mod diffuse_light;
 pub mod lambertian;
 pub mod metal;
 
 pub trait Scatterable {
-    fn scatter(
-        &self,
-        r_in: &Ray,
-        rec: &HitRecord,
-        attenuation: &mut Vec3,
-        scattered: &mut Ray,
-    ) -> bool;
+    // the attenuation and the scattered ray, None if the light is absorbed
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, sampler: &mut Sampler) -> Option<(Vec3, Ray)>;
+
+    fn emitted(&self, _rec: &HitRecord) -> Vec3 {
+        Vec3::new(0.0, 0.0, 0.0)
+    }
+
+    // surface color at the hit, without any lighting, for the albedo pass
+    fn albedo(&self, rec: &HitRecord) -> Vec3;
+
+    // the bsdf times the cosine with the normal, for light arriving from `direction` and
+    // leaving back along `r_in`
+    fn eval(&self, _r_in: &Ray, _rec: &HitRecord, _direction: Vec3) -> Vec3 {
+        Vec3::new(0.0, 0.0, 0.0)
+    }
+
+    // the solid angle density of `scatter` picking `direction`
+    fn pdf(&self, _r_in: &Ray, _rec: &HitRecord, _direction: Vec3) -> f64 {
+        0.0
+    }
+
+    // specular surfaces, like mirrors and glass, only scatter toward the one direction
+    // `scatter` picks and `eval` and `pdf` are zero everywhere. lights can't be sampled for
+    // them.
+    fn is_specular(&self) -> bool {
+        true
+    }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Material {
     Lambertian(lambertian::Lambertian),
     Metal(metal::Metal),
     Dielectric(dielectric::Dielectric),
+    DiffuseLight(diffuse_light::DiffuseLight),
+}
+
+impl Material {
+    // whether hits on it give off light, which makes its object a light to sample
+    pub fn is_emissive(&self) -> bool {
+        matches!(*self, Material::DiffuseLight(_))
+    }
 }
 
 impl Scatterable for Material {
-    fn scatter(
-        &self,
-        r_in: &Ray,
-        rec: &HitRecord,
-        attenuation: &mut Vec3,
-        scattered: &mut Ray,
-    ) -> bool {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, sampler: &mut Sampler) -> Option<(Vec3, Ray)> {
+        match *self {
+            Material::Lambertian(ref inner) => inner.scatter(r_in, rec, sampler),
+            Material::Metal(ref inner) => inner.scatter(r_in, rec, sampler),
+            Material::Dielectric(ref inner) => inner.scatter(r_in, rec, sampler),
+            Material::DiffuseLight(ref inner) => inner.scatter(r_in, rec, sampler),
+        }
+    }
+
+    fn emitted(&self, rec: &HitRecord) -> Vec3 {
         match *self {
-            Material::Lambertian(ref inner) => inner.scatter(r_in, rec, attenuation, scattered),
-            Material::Metal(ref inner) => inner.scatter(r_in, rec, attenuation, scattered),
-            Material::Dielectric(ref inner) => inner.scatter(r_in, rec, attenuation, scattered),
+            Material::Lambertian(ref inner) => inner.emitted(rec),
+            Material::Metal(ref inner) => inner.emitted(rec),
+            Material::Dielectric(ref inner) => inner.emitted(rec),
+            Material::DiffuseLight(ref inner) => inner.emitted(rec),
+        }
+    }
+
+    fn albedo(&self, rec: &HitRecord) -> Vec3 {
+        match *self {
+            Material::Lambertian(ref inner) => inner.albedo(rec),
+            Material::Metal(ref inner) => inner.albedo(rec),
+            Material::Dielectric(ref inner) => inner.albedo(rec),
+            Material::DiffuseLight(ref inner) => inner.albedo(rec),
+        }
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, direction: Vec3) -> Vec3 {
+        match *self {
+            Material::Lambertian(ref inner) => inner.eval(r_in, rec, direction),
+            Material::Metal(ref inner) => inner.eval(r_in, rec, direction),
+            Material::Dielectric(ref inner) => inner.eval(r_in, rec, direction),
+            Material::DiffuseLight(ref inner) => inner.eval(r_in, rec, direction),
+        }
+    }
+
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, direction: Vec3) -> f64 {
+        match *self {
+            Material::Lambertian(ref inner) => inner.pdf(r_in, rec, direction),
+            Material::Metal(ref inner) => inner.pdf(r_in, rec, direction),
+            Material::Dielectric(ref inner) => inner.pdf(r_in, rec, direction),
+            Material::DiffuseLight(ref inner) => inner.pdf(r_in, rec, direction),
+        }
+    }
+
+    fn is_specular(&self) -> bool {
+        match *self {
+            Material::Lambertian(ref inner) => inner.is_specular(),
+            Material::Metal(ref inner) => inner.is_specular(),
+            Material::Dielectric(ref inner) => inner.is_specular(),
+            Material::DiffuseLight(ref inner) => inner.is_specular(),
         }
     }
 }
@@ -51,7 +121,7 @@ fn reflect(v: Vec3, n: Vec3) -> Vec3 {
 
 fn refract(v: &Vec3, n: &Vec3, ni_over_nt: f64, refracted: &mut Vec3) -> bool {
     let uv = Vec3::unit_vector(*v);
-    let dt = Vec3::dot(&uv, &n);
+    let dt = Vec3::dot(&uv, n);
 
     let discriminant = 1.0 - ni_over_nt * ni_over_nt * (1.0 - dt * dt);
 
@@ -68,3 +138,55 @@ fn schlick(cosine: f64, ref_idx: f64) -> f64 {
     r0 = r0 * r0;
     r0 + (1.0 - r0) * f64::powf(1.0 - cosine, 5.0)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::random_unit_vector;
+
+    #[test]
+    fn pdfs_match_scatter() {
+        let glass = Material::Dielectric(dielectric::Dielectric::new(1.5));
+        let rec = HitRecord::new(
+            1.0,
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            &glass,
+        );
+        let r_in = Ray::new(Vec3::new(-1.0, 1.0, 0.0), Vec3::new(1.0, -1.0, 0.0));
+        let albedo = Vec3::new(0.2, 0.4, 0.8);
+        let materials = [
+            Material::Lambertian(lambertian::Lambertian::new(albedo)),
+            Material::Metal(metal::Metal::new(albedo, 0.4).with_phong_lobe()),
+        ];
+
+        let mut sampler = Sampler::new(9);
+        for material in &materials {
+            assert!(!material.is_specular());
+
+            // scattering keeps the albedo, which is the bsdf over the density
+            for _ in 0..100 {
+                if let Some((attenuation, scattered)) = material.scatter(&r_in, &rec, &mut sampler)
+                {
+                    let pdf = material.pdf(&r_in, &rec, scattered.direction);
+                    let f = material.eval(&r_in, &rec, scattered.direction);
+                    assert!(pdf > 0.0);
+                    assert!((f / pdf - attenuation).length() < 1e-9);
+                }
+            }
+
+            // and the density covers the sphere once
+            let n = 200_000;
+            let total = (0..n).fold(0.0, |total, _| {
+                total + material.pdf(&r_in, &rec, random_unit_vector(&mut sampler))
+            });
+            let integral = total * 4.0 * std::f64::consts::PI / f64::from(n);
+            assert!(integral <= 1.0 + 0.02 && integral > 0.9, "{}", integral);
+        }
+
+        let mirror = Material::Metal(metal::Metal::new(albedo, 0.0));
+        assert!(mirror.is_specular());
+        assert_eq!(mirror.pdf(&r_in, &rec, Vec3::new(1.0, 1.0, 0.0)), 0.0);
+        assert!(glass.is_specular());
+    }
+}
@@ -0,0 +1,588 @@
+// wavefront .obj/.mtl loader. faces are fan triangulated, so polygons are expected to be
+// convex. every (group, material) pair becomes its own mesh.
+
+use crate::error::Error;
+use crate::materials::{
+    dielectric::Dielectric, diffuse_light::DiffuseLight, lambertian::Lambertian, metal::Metal,
+    Material,
+};
+use crate::reflexible::mesh::TriangleMesh;
+use crate::vec3::Vec3;
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+pub struct ObjMesh {
+    pub name: String,
+    pub material: Arc<Material>,
+    pub mesh: TriangleMesh,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MtlMaterial {
+    pub kd: Vec3,
+    pub ks: Vec3,
+    pub ke: Vec3,
+    pub ns: f64,
+    pub ni: f64,
+    pub d: f64,
+    pub illum: u32,
+}
+
+impl Default for MtlMaterial {
+    fn default() -> MtlMaterial {
+        MtlMaterial {
+            kd: Vec3::new(0.8, 0.8, 0.8),
+            ks: Vec3::new(0.0, 0.0, 0.0),
+            ke: Vec3::new(0.0, 0.0, 0.0),
+            ns: 0.0,
+            ni: 1.0,
+            d: 1.0,
+            illum: 2,
+        }
+    }
+}
+
+impl MtlMaterial {
+    // emissive materials become lights, transparent ones glass, mirror-like ones metal
+    // and the rest diffuse
+    pub fn to_material(&self) -> Material {
+        if max_component(&self.ke) > 0.0 {
+            return Material::DiffuseLight(DiffuseLight::new(self.ke));
+        }
+
+        let transparent = self.d < 1.0 || self.illum == 4 || self.illum == 6 || self.illum == 7;
+        if transparent {
+            let ref_idx = if self.ni > 1.0 { self.ni } else { 1.5 };
+            return Material::Dielectric(Dielectric::new(ref_idx));
+        }
+
+        let specular = max_component(&self.ks);
+        if specular > 0.0 && (self.illum == 3 || specular > max_component(&self.kd)) {
+            // blinn-phong exponent to roughness
+            let fuzz = f64::sqrt(2.0 / (self.ns.max(0.0) + 2.0));
+            return Material::Metal(Metal::new(self.ks, fuzz));
+        }
+
+        Material::Lambertian(Lambertian::new(self.kd))
+    }
+}
+
+pub fn load_obj<P: AsRef<Path>, M: Into<Arc<Material>>>(
+    path: P,
+    default_material: M,
+) -> Result<Vec<ObjMesh>, Error> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path)?;
+    let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+    parse_obj(
+        &source,
+        &path.display().to_string(),
+        base_dir,
+        default_material,
+    )
+}
+
+pub fn load_mtl<P: AsRef<Path>>(path: P) -> Result<HashMap<String, MtlMaterial>, Error> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path)?;
+
+    parse_mtl(&source, &path.display().to_string())
+}
+
+// `file` is only used in error messages, `mtllib` paths are resolved against `base_dir`
+pub fn parse_obj<M: Into<Arc<Material>>>(
+    source: &str,
+    file: &str,
+    base_dir: &Path,
+    default_material: M,
+) -> Result<Vec<ObjMesh>, Error> {
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut materials: HashMap<String, MtlMaterial> = HashMap::new();
+
+    let mut builders: Vec<MeshBuilder> = Vec::new();
+    let mut builder_index: HashMap<(String, String), usize> = HashMap::new();
+    let mut group = String::from("default");
+    let mut material_name = String::new();
+
+    for (line_index, raw_line) in source.lines().enumerate() {
+        let line_no = line_index + 1;
+        let err = |message: String| parse_error(file, line_no, message);
+
+        let line = strip_comment(raw_line);
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        match keyword {
+            "v" => positions.push(parse_vec3(&args, &err)?),
+            "vn" => normals.push(parse_vec3(&args, &err)?),
+            "vt" => {
+                if args.is_empty() {
+                    return Err(err("expected at least 1 texture coordinate".to_string()));
+                }
+                let u = parse_f64(args[0], &err)?;
+                let v = match args.get(1) {
+                    Some(v) => parse_f64(v, &err)?,
+                    None => 0.0,
+                };
+                uvs.push((u, v));
+            }
+            "f" => {
+                if args.len() < 3 {
+                    return Err(err(format!(
+                        "face needs at least 3 vertices, got {}",
+                        args.len()
+                    )));
+                }
+                let mut face = Vec::with_capacity(args.len());
+                for arg in &args {
+                    face.push(parse_face_vertex(
+                        arg,
+                        positions.len(),
+                        uvs.len(),
+                        normals.len(),
+                        &err,
+                    )?);
+                }
+
+                let key = (group.clone(), material_name.clone());
+                let index = *builder_index.entry(key).or_insert_with(|| {
+                    builders.push(MeshBuilder::new(&group, &material_name));
+                    builders.len() - 1
+                });
+                builders[index].add_face(&face, &positions, &uvs, &normals);
+            }
+            "g" | "o" => {
+                group = if args.is_empty() {
+                    String::from("default")
+                } else {
+                    args.join(" ")
+                };
+            }
+            // names missing from the material libraries get the default material
+            "usemtl" => material_name = args.join(" "),
+            "mtllib" => {
+                for lib in &args {
+                    let lib_path = base_dir.join(lib);
+                    let source = fs::read_to_string(&lib_path).map_err(|e| {
+                        err(format!(
+                            "can't read material library '{}': {}",
+                            lib_path.display(),
+                            e
+                        ))
+                    })?;
+                    materials.extend(parse_mtl(&source, &lib_path.display().to_string())?);
+                }
+            }
+            // smoothing groups, lines, points and free-form geometry are not supported
+            _ => {}
+        }
+    }
+
+    // the meshes of one material share it
+    let default_material = default_material.into();
+    let mut shared: HashMap<String, Arc<Material>> = HashMap::new();
+    builders
+        .into_iter()
+        .map(|builder| {
+            let material = match materials.get(&builder.material) {
+                Some(mtl) => shared
+                    .entry(builder.material.clone())
+                    .or_insert_with(|| Arc::new(mtl.to_material()))
+                    .clone(),
+                None => default_material.clone(),
+            };
+            builder.build(material)
+        })
+        .collect()
+}
+
+pub fn parse_mtl(source: &str, file: &str) -> Result<HashMap<String, MtlMaterial>, Error> {
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, MtlMaterial)> = None;
+
+    for (line_index, raw_line) in source.lines().enumerate() {
+        let line_no = line_index + 1;
+        let err = |message: String| parse_error(file, line_no, message);
+
+        let line = strip_comment(raw_line);
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        if keyword == "newmtl" {
+            if args.is_empty() {
+                return Err(err("newmtl without a name".to_string()));
+            }
+            if let Some((name, mtl)) = current.take() {
+                materials.insert(name, mtl);
+            }
+            current = Some((args.join(" "), MtlMaterial::default()));
+            continue;
+        }
+
+        let mtl = match current {
+            Some((_, ref mut mtl)) => mtl,
+            None => return Err(err(format!("'{}' before any newmtl", keyword))),
+        };
+        match keyword {
+            "Kd" => mtl.kd = parse_vec3(&args, &err)?,
+            "Ks" => mtl.ks = parse_vec3(&args, &err)?,
+            "Ke" => mtl.ke = parse_vec3(&args, &err)?,
+            "Ns" => mtl.ns = parse_single(&args, &err)?,
+            "Ni" => mtl.ni = parse_single(&args, &err)?,
+            "d" => mtl.d = parse_single(&args, &err)?,
+            "Tr" => mtl.d = 1.0 - parse_single(&args, &err)?,
+            "illum" => {
+                let illum = parse_single(&args, &err)?;
+                if illum < 0.0 || illum.fract() != 0.0 {
+                    return Err(err(format!("invalid illumination model '{}'", args[0])));
+                }
+                mtl.illum = illum as u32;
+            }
+            // ambient and texture maps are ignored
+            _ => {}
+        }
+    }
+
+    if let Some((name, mtl)) = current {
+        materials.insert(name, mtl);
+    }
+
+    Ok(materials)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct FaceVertex {
+    position: usize,
+    uv: Option<usize>,
+    normal: Option<usize>,
+}
+
+struct MeshBuilder {
+    name: String,
+    material: String,
+    positions: Vec<Vec3>,
+    uvs: Vec<Option<(f64, f64)>>,
+    normals: Vec<Option<Vec3>>,
+    indices: Vec<[usize; 3]>,
+    vertex_index: HashMap<FaceVertex, usize>,
+}
+
+impl MeshBuilder {
+    fn new(group: &str, material: &str) -> MeshBuilder {
+        let name = if material.is_empty() {
+            group.to_string()
+        } else {
+            format!("{}:{}", group, material)
+        };
+
+        MeshBuilder {
+            name,
+            material: material.to_string(),
+            positions: Vec::new(),
+            uvs: Vec::new(),
+            normals: Vec::new(),
+            indices: Vec::new(),
+            vertex_index: HashMap::new(),
+        }
+    }
+
+    fn add_face(
+        &mut self,
+        face: &[FaceVertex],
+        positions: &[Vec3],
+        uvs: &[(f64, f64)],
+        normals: &[Vec3],
+    ) {
+        let indices: Vec<usize> = face
+            .iter()
+            .map(|fv| self.vertex(fv, positions, uvs, normals))
+            .collect();
+
+        for k in 1..indices.len() - 1 {
+            self.indices.push([indices[0], indices[k], indices[k + 1]]);
+        }
+    }
+
+    fn vertex(
+        &mut self,
+        fv: &FaceVertex,
+        positions: &[Vec3],
+        uvs: &[(f64, f64)],
+        normals: &[Vec3],
+    ) -> usize {
+        if let Some(&index) = self.vertex_index.get(fv) {
+            return index;
+        }
+
+        let index = self.positions.len();
+        self.positions.push(positions[fv.position]);
+        self.uvs.push(fv.uv.map(|i| uvs[i]));
+        self.normals.push(fv.normal.map(|i| normals[i]));
+        self.vertex_index.insert(*fv, index);
+
+        index
+    }
+
+    // normals and uvs are only kept when every vertex of the mesh has one
+    fn build(self, material: Arc<Material>) -> Result<ObjMesh, Error> {
+        let normals: Option<Vec<Vec3>> = self.normals.into_iter().collect();
+        let uvs: Option<Vec<(f64, f64)>> = self.uvs.into_iter().collect();
+
+        Ok(ObjMesh {
+            name: self.name,
+            material: material.clone(),
+            mesh: TriangleMesh::new(self.positions, normals, uvs, self.indices, material)?,
+        })
+    }
+}
+
+fn parse_error(file: &str, line: usize, message: String) -> Error {
+    Error::Parse {
+        file: file.to_string(),
+        line,
+        message,
+    }
+}
+
+fn strip_comment(line: &str) -> &str {
+    match line.find('#') {
+        Some(i) => &line[..i],
+        None => line,
+    }
+}
+
+fn parse_f64<F: Fn(String) -> Error>(token: &str, err: &F) -> Result<f64, Error> {
+    token
+        .parse::<f64>()
+        .map_err(|_| err(format!("invalid number '{}'", token)))
+}
+
+fn parse_single<F: Fn(String) -> Error>(args: &[&str], err: &F) -> Result<f64, Error> {
+    match args.first() {
+        Some(token) => parse_f64(token, err),
+        None => Err(err("expected a number".to_string())),
+    }
+}
+
+fn parse_vec3<F: Fn(String) -> Error>(args: &[&str], err: &F) -> Result<Vec3, Error> {
+    if args.len() < 3 {
+        return Err(err(format!("expected 3 numbers, got {}", args.len())));
+    }
+
+    Ok(Vec3::new(
+        parse_f64(args[0], err)?,
+        parse_f64(args[1], err)?,
+        parse_f64(args[2], err)?,
+    ))
+}
+
+// `v`, `v/vt`, `v//vn` or `v/vt/vn`, with 1-based or negative (relative) indices
+fn parse_face_vertex<F: Fn(String) -> Error>(
+    token: &str,
+    position_count: usize,
+    uv_count: usize,
+    normal_count: usize,
+    err: &F,
+) -> Result<FaceVertex, Error> {
+    let mut parts = token.split('/');
+
+    let position = match parts.next() {
+        Some(p) if !p.is_empty() => resolve_index(p, position_count, "vertex", err)?,
+        _ => return Err(err(format!("invalid face vertex '{}'", token))),
+    };
+    let uv = match parts.next() {
+        Some(t) if !t.is_empty() => Some(resolve_index(t, uv_count, "texture coordinate", err)?),
+        _ => None,
+    };
+    let normal = match parts.next() {
+        Some(n) if !n.is_empty() => Some(resolve_index(n, normal_count, "normal", err)?),
+        _ => None,
+    };
+    if parts.next().is_some() {
+        return Err(err(format!("invalid face vertex '{}'", token)));
+    }
+
+    Ok(FaceVertex {
+        position,
+        uv,
+        normal,
+    })
+}
+
+fn resolve_index<F: Fn(String) -> Error>(
+    token: &str,
+    count: usize,
+    what: &str,
+    err: &F,
+) -> Result<usize, Error> {
+    let index: i64 = token
+        .parse()
+        .map_err(|_| err(format!("invalid {} index '{}'", what, token)))?;
+
+    let resolved = if index > 0 {
+        index - 1
+    } else {
+        count as i64 + index
+    };
+    if index == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(err(format!(
+            "{} index {} out of range, {} defined so far",
+            what, index, count
+        )));
+    }
+
+    Ok(resolved as usize)
+}
+
+fn max_component(v: &Vec3) -> f64 {
+    v.x.max(v.y).max(v.z)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ray::Ray;
+    use crate::reflexible::Reflexible;
+
+    fn default_material() -> Material {
+        Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)))
+    }
+
+    #[test]
+    fn quad_with_groups() {
+        let source = "
+            # a unit quad split into two groups
+            v -1 -1 0
+            v 1 -1 0
+            v 1 1 0
+            v -1 1 0
+            vt 0 0
+            vt 1 0
+            vt 1 1
+            vt 0 1
+            vn 0 0 1
+            g first
+            f 1/1/1 2/2/1 3/3/1 4/4/1
+            g second
+            f -4//-1 -2//-1 -1//-1
+        ";
+        let meshes = parse_obj(source, "quad.obj", Path::new(""), default_material()).unwrap();
+
+        assert_eq!(meshes.len(), 2);
+        assert_eq!(meshes[0].name, "first");
+        assert_eq!(meshes[0].mesh.triangle_count(), 2);
+        assert_eq!(meshes[1].mesh.triangle_count(), 1);
+
+        let r = Ray::new(Vec3::new(0.5, -0.5, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        let rec = meshes[0].mesh.hit(&r, 0.001, f64::MAX).unwrap();
+        assert!((rec.u - 0.75).abs() < 1e-12 && (rec.v - 0.25).abs() < 1e-12);
+    }
+
+    #[test]
+    fn errors_carry_line_numbers() {
+        let source = "v 0 0 0\nv 1 0 0\nf 1 2 3\n";
+        match parse_obj(source, "bad.obj", Path::new(""), default_material()) {
+            Err(Error::Parse { file, line, .. }) => {
+                assert_eq!(file, "bad.obj");
+                assert_eq!(line, 3);
+            }
+            _ => panic!("expected a parse error"),
+        }
+
+        match parse_obj("v 0 zero 0\n", "bad.obj", Path::new(""), default_material()) {
+            Err(Error::Parse { line, message, .. }) => {
+                assert_eq!(line, 1);
+                assert_eq!(message, "invalid number 'zero'");
+            }
+            _ => panic!("expected a parse error"),
+        }
+    }
+
+    #[test]
+    fn unknown_materials() {
+        let source = "v 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl nope\nf 1 2 3\n";
+        let meshes = parse_obj(source, "test.obj", Path::new(""), default_material()).unwrap();
+
+        assert_eq!(meshes.len(), 1);
+        assert_eq!(meshes[0].name, "default:nope");
+        assert_eq!(*meshes[0].material, default_material());
+    }
+
+    #[test]
+    fn mtl_materials() {
+        let source = "
+            newmtl matte
+            Kd 0.1 0.2 0.3
+            newmtl chrome
+            Kd 0.1 0.1 0.1
+            Ks 0.9 0.9 0.9
+            Ns 998
+            newmtl glass
+            Ni 1.45
+            d 0.2
+            newmtl lamp
+            Ke 4 4 4
+        ";
+        let materials = parse_mtl(source, "test.mtl").unwrap();
+
+        assert_eq!(
+            materials["matte"].to_material(),
+            Material::Lambertian(Lambertian::new(Vec3::new(0.1, 0.2, 0.3)))
+        );
+        assert_eq!(
+            materials["chrome"].to_material(),
+            Material::Metal(Metal::new(
+                Vec3::new(0.9, 0.9, 0.9),
+                f64::sqrt(2.0 / 1000.0)
+            ))
+        );
+        assert_eq!(
+            materials["glass"].to_material(),
+            Material::Dielectric(Dielectric::new(1.45))
+        );
+        assert_eq!(
+            materials["lamp"].to_material(),
+            Material::DiffuseLight(DiffuseLight::new(Vec3::new(4.0, 4.0, 4.0)))
+        );
+
+        match parse_mtl("Kd 1 1 1\n", "test.mtl") {
+            Err(Error::Parse { line, .. }) => assert_eq!(line, 1),
+            _ => panic!("expected a parse error"),
+        }
+    }
+
+    #[test]
+    fn mtllib() {
+        let dir = std::env::temp_dir().join("ray_tracer_obj_test");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("scene.mtl"), "newmtl red\nKd 1 0 0\n").unwrap();
+        fs::write(
+            dir.join("scene.obj"),
+            "mtllib scene.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl red\nf 1 2 3\n",
+        )
+        .unwrap();
+
+        let meshes = load_obj(dir.join("scene.obj"), default_material()).unwrap();
+
+        assert_eq!(meshes.len(), 1);
+        assert_eq!(meshes[0].name, "default:red");
+        assert_eq!(
+            *meshes[0].material,
+            Material::Lambertian(Lambertian::new(Vec3::new(1.0, 0.0, 0.0)))
+        );
+    }
+}
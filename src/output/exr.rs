@@ -0,0 +1,157 @@
+// minimal openexr writer: single part, scanline, uncompressed, 32-bit float channels
+
+use std::io::{self, Write};
+
+const MAGIC: [u8; 4] = [0x76, 0x2f, 0x31, 0x01];
+const VERSION: [u8; 4] = [2, 0, 0, 0];
+const PIXEL_TYPE_FLOAT: i32 = 2;
+
+// one value per pixel, rows from the top
+pub struct Channel<'a> {
+    pub name: String,
+    pub data: &'a [f32],
+}
+
+impl<'a> Channel<'a> {
+    pub fn new(name: &str, data: &'a [f32]) -> Channel<'a> {
+        Channel {
+            name: name.to_string(),
+            data,
+        }
+    }
+}
+
+pub fn write<W: Write>(
+    w: &mut W,
+    width: u32,
+    height: u32,
+    channels: &mut [Channel],
+) -> io::Result<()> {
+    for channel in channels.iter() {
+        if channel.data.len() != (width * height) as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("channel '{}' doesn't match the image size", channel.name),
+            ));
+        }
+    }
+    // readers expect the channel list sorted by name
+    channels.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&MAGIC);
+    header.extend_from_slice(&VERSION);
+
+    let mut chlist = Vec::new();
+    for channel in channels.iter() {
+        chlist.extend_from_slice(channel.name.as_bytes());
+        chlist.push(0);
+        chlist.extend_from_slice(&PIXEL_TYPE_FLOAT.to_le_bytes());
+        // pLinear and three reserved bytes
+        chlist.extend_from_slice(&[0, 0, 0, 0]);
+        chlist.extend_from_slice(&1i32.to_le_bytes());
+        chlist.extend_from_slice(&1i32.to_le_bytes());
+    }
+    chlist.push(0);
+
+    let mut window = Vec::new();
+    for v in &[0, 0, width as i32 - 1, height as i32 - 1] {
+        window.extend_from_slice(&v.to_le_bytes());
+    }
+
+    attribute(&mut header, "channels", "chlist", &chlist);
+    attribute(&mut header, "compression", "compression", &[0]);
+    attribute(&mut header, "dataWindow", "box2i", &window);
+    attribute(&mut header, "displayWindow", "box2i", &window);
+    attribute(&mut header, "lineOrder", "lineOrder", &[0]);
+    attribute(
+        &mut header,
+        "pixelAspectRatio",
+        "float",
+        &1f32.to_le_bytes(),
+    );
+    attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8]);
+    attribute(
+        &mut header,
+        "screenWindowWidth",
+        "float",
+        &1f32.to_le_bytes(),
+    );
+    header.push(0);
+
+    // one scanline per block: y, byte count, then each channel's row in turn
+    let row_bytes = 4 * width as u64 * channels.len() as u64;
+    let block_size = 8 + row_bytes;
+    let first_block = header.len() as u64 + 8 * u64::from(height);
+
+    w.write_all(&header)?;
+    for y in 0..u64::from(height) {
+        w.write_all(&(first_block + y * block_size).to_le_bytes())?;
+    }
+
+    let mut block = Vec::with_capacity(block_size as usize);
+    for y in 0..height as usize {
+        block.clear();
+        block.extend_from_slice(&(y as i32).to_le_bytes());
+        block.extend_from_slice(&(row_bytes as i32).to_le_bytes());
+        for channel in channels.iter() {
+            let row = &channel.data[y * width as usize..(y + 1) * width as usize];
+            for v in row {
+                block.extend_from_slice(&v.to_le_bytes());
+            }
+        }
+        w.write_all(&block)?;
+    }
+
+    Ok(())
+}
+
+fn attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    header.extend_from_slice(name.as_bytes());
+    header.push(0);
+    header.extend_from_slice(kind.as_bytes());
+    header.push(0);
+    header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    header.extend_from_slice(value);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn read_u64(bytes: &[u8], at: usize) -> u64 {
+        let mut b = [0; 8];
+        b.copy_from_slice(&bytes[at..at + 8]);
+        u64::from_le_bytes(b)
+    }
+
+    #[test]
+    fn layout() {
+        let r = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let g = [0.5; 6];
+        let mut channels = [Channel::new("R", &r), Channel::new("G", &g)];
+
+        let mut out = Vec::new();
+        write(&mut out, 3, 2, &mut channels).unwrap();
+
+        assert_eq!(&out[..4], &MAGIC);
+        let header_end = out.len() - 2 * 8 - 2 * (8 + 2 * 3 * 4);
+        assert_eq!(out[header_end - 1], 0);
+
+        // the second block holds row 1, with G sorted before R
+        let offset = read_u64(&out, header_end + 8) as usize;
+        assert_eq!(&out[offset..offset + 4], &1i32.to_le_bytes());
+        assert_eq!(&out[offset + 4..offset + 8], &24i32.to_le_bytes());
+        assert_eq!(&out[offset + 8..offset + 12], &0.5f32.to_le_bytes());
+        assert_eq!(&out[offset + 20..offset + 24], &4.0f32.to_le_bytes());
+        assert_eq!(offset + 32, out.len());
+    }
+
+    #[test]
+    fn size_mismatch() {
+        let r = [1.0, 2.0];
+        let mut channels = [Channel::new("R", &r)];
+
+        assert!(write(&mut Vec::new(), 3, 2, &mut channels).is_err());
+    }
+}
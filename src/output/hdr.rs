@@ -0,0 +1,93 @@
+// radiance rgbe (.hdr) writer, uncompressed scanlines
+
+use crate::framebuffer::Framebuffer;
+use crate::vec3::Vec3;
+
+use std::io::{self, Write};
+
+pub fn write<W: Write>(w: &mut W, fb: &Framebuffer) -> io::Result<()> {
+    write!(
+        w,
+        "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+        fb.height(),
+        fb.width()
+    )?;
+
+    let mut row = Vec::with_capacity(4 * fb.width() as usize);
+    for y in 0..fb.height() {
+        row.clear();
+        for x in 0..fb.width() {
+            row.extend_from_slice(&rgbe(fb.get(x, y)));
+        }
+        w.write_all(&row)?;
+    }
+
+    Ok(())
+}
+
+// shared exponent encoding, the largest component keeps 8 bits of mantissa. exponents
+// only go from -127 to 127, values past that are written as the largest there is.
+pub fn rgbe(col: Vec3) -> [u8; 4] {
+    // infinity counts as the largest float, nan as black
+    let channel = |c: f64| {
+        if c.is_nan() {
+            0.0
+        } else {
+            c.clamp(0.0, f64::MAX)
+        }
+    };
+    let (r, g, b) = (channel(col.r()), channel(col.g()), channel(col.b()));
+    let v = r.max(g).max(b);
+    if v < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+
+    // v = m * 2^e with m in [0.5, 1)
+    let mut e = v.log2().floor() as i32 + 1;
+    if v / 2f64.powi(e) >= 1.0 {
+        e += 1;
+    } else if v / 2f64.powi(e) < 0.5 {
+        e -= 1;
+    }
+    let e = e.clamp(-127, 127);
+    let scale = 256.0 / 2f64.powi(e);
+    let mantissa = |c: f64| (c * scale).min(255.0) as u8;
+
+    [mantissa(r), mantissa(g), mantissa(b), (e + 128) as u8]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn encode() {
+        assert_eq!(rgbe(Vec3::new(1.0, 1.0, 1.0)), [128, 128, 128, 129]);
+        assert_eq!(rgbe(Vec3::new(0.5, 0.25, 0.0)), [128, 64, 0, 128]);
+        assert_eq!(rgbe(Vec3::new(3.0, 0.0, 0.0)), [192, 0, 0, 130]);
+        assert_eq!(rgbe(Vec3::new(0.0, 0.0, 0.0)), [0, 0, 0, 0]);
+        assert_eq!(rgbe(Vec3::new(f64::NAN, 0.5, 0.0)), [0, 128, 0, 128]);
+    }
+
+    #[test]
+    fn saturate() {
+        let big = 2f64.powi(127);
+        assert_eq!(rgbe(Vec3::new(big, big / 2.0, 0.0)), [255, 128, 0, 255]);
+        assert_eq!(rgbe(Vec3::new(1e300, 0.0, 0.0)), [255, 0, 0, 255]);
+        assert_eq!(rgbe(Vec3::new(f64::INFINITY, 1.0, 0.0)), [255, 0, 0, 255]);
+        // just below the cut off still decodes to the same value
+        let below = 2f64.powi(126) * 1.5;
+        assert_eq!(rgbe(Vec3::new(below, 0.0, 0.0)), [192, 0, 0, 255]);
+    }
+
+    #[test]
+    fn header() {
+        let fb = Framebuffer::new(3, 2);
+        let mut out = Vec::new();
+        write(&mut out, &fb).unwrap();
+
+        let header = "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 2 +X 3\n";
+        assert!(out.starts_with(header.as_bytes()));
+        assert_eq!(out.len(), header.len() + 3 * 2 * 4);
+    }
+}
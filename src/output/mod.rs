@@ -0,0 +1,300 @@
+use crate::accumulator::Accumulator;
+use crate::aov::{Aov, Aovs};
+use crate::error::Error;
+use crate::framebuffer::Framebuffer;
+use crate::progress::{CancelToken, Progress, ProgressObserver};
+use crate::tonemap::{ToneMapping, Transfer};
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+pub mod exr;
+pub mod hdr;
+pub mod pfm;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    Png,
+    Jpeg,
+    Bmp,
+    Ppm,
+    Exr,
+    Hdr,
+    Pfm,
+}
+
+impl OutputFormat {
+    pub fn from_extension(ext: &str) -> Result<OutputFormat, Error> {
+        match &*ext.to_ascii_lowercase() {
+            "png" => Ok(OutputFormat::Png),
+            "jpg" | "jpeg" => Ok(OutputFormat::Jpeg),
+            "bmp" => Ok(OutputFormat::Bmp),
+            "ppm" => Ok(OutputFormat::Ppm),
+            "exr" => Ok(OutputFormat::Exr),
+            "hdr" => Ok(OutputFormat::Hdr),
+            "pfm" => Ok(OutputFormat::Pfm),
+            _ => Err(Error::UnsupportedFormat(ext.to_string())),
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Result<OutputFormat, Error> {
+        let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("");
+        OutputFormat::from_extension(ext)
+    }
+
+    // floating point formats store the linear framebuffer, without tone mapping
+    pub fn is_hdr(&self) -> bool {
+        match *self {
+            OutputFormat::Exr | OutputFormat::Hdr | OutputFormat::Pfm => true,
+            OutputFormat::Png | OutputFormat::Jpeg | OutputFormat::Bmp | OutputFormat::Ppm => false,
+        }
+    }
+}
+
+// saves the framebuffer, choosing the format from the file extension
+pub fn save<P: AsRef<Path>>(
+    fb: &Framebuffer,
+    path: P,
+    tone_mapping: &ToneMapping,
+) -> Result<(), Error> {
+    let format = OutputFormat::from_path(path.as_ref())?;
+    save_as(fb, path, format, tone_mapping)
+}
+
+pub fn save_as<P: AsRef<Path>>(
+    fb: &Framebuffer,
+    path: P,
+    format: OutputFormat,
+    tone_mapping: &ToneMapping,
+) -> Result<(), Error> {
+    let mut out = BufWriter::new(File::create(path)?);
+
+    let image_format = match format {
+        OutputFormat::Exr => {
+            let (r, g, b) = (channel(fb, 0), channel(fb, 1), channel(fb, 2));
+            let mut channels = [
+                exr::Channel::new("R", &r),
+                exr::Channel::new("G", &g),
+                exr::Channel::new("B", &b),
+            ];
+            exr::write(&mut out, fb.width(), fb.height(), &mut channels)?;
+            return Ok(out.flush()?);
+        }
+        OutputFormat::Hdr => {
+            hdr::write(&mut out, fb)?;
+            return Ok(out.flush()?);
+        }
+        OutputFormat::Pfm => {
+            pfm::write(&mut out, fb)?;
+            return Ok(out.flush()?);
+        }
+        OutputFormat::Png => image::ImageOutputFormat::PNG,
+        OutputFormat::Jpeg => image::ImageOutputFormat::JPEG(75),
+        OutputFormat::Bmp => image::ImageOutputFormat::BMP,
+        OutputFormat::Ppm => image::ImageOutputFormat::PNM(image::pnm::PNMSubtype::Pixmap(
+            image::pnm::SampleEncoding::Binary,
+        )),
+    };
+
+    let img = image::ImageRgb8(fb.to_rgb8(tone_mapping));
+    img.write_to(&mut out, image_format)?;
+    out.flush()?;
+
+    Ok(())
+}
+
+// saves the image to `path` and each aov next to it as `<name>.<aov>.<ext>`, except for
+// exr where the aovs become layers of the same file
+pub fn save_with_aovs<P: AsRef<Path>>(
+    fb: &Framebuffer,
+    aovs: &Aovs,
+    path: P,
+    format: OutputFormat,
+    tone_mapping: &ToneMapping,
+) -> Result<(), Error> {
+    let path = path.as_ref();
+
+    if format == OutputFormat::Exr {
+        let mut layers = vec![
+            ("R".to_string(), channel(fb, 0)),
+            ("G".to_string(), channel(fb, 1)),
+            ("B".to_string(), channel(fb, 2)),
+        ];
+        for (aov, buffer) in aovs.iter() {
+            for (index, name) in aov.channels().iter().enumerate() {
+                layers.push((format!("{}.{}", aov.name(), name), channel(buffer, index)));
+            }
+        }
+
+        let mut channels: Vec<_> = layers
+            .iter()
+            .map(|(name, data)| exr::Channel::new(name, data))
+            .collect();
+        let mut out = BufWriter::new(File::create(path)?);
+        exr::write(&mut out, fb.width(), fb.height(), &mut channels)?;
+        return Ok(out.flush()?);
+    }
+
+    save_as(fb, path, format, tone_mapping)?;
+    for (aov, buffer) in aovs.iter() {
+        let aov_path = aov_path(path, aov);
+        if format.is_hdr() {
+            save_as(buffer, aov_path, format, tone_mapping)?;
+            continue;
+        }
+
+        // albedo is a color like the image, the rest are data and stay linear
+        let aov_tone_mapping = match aov {
+            Aov::Albedo => *tone_mapping,
+            _ => ToneMapping {
+                transfer: Transfer::Gamma(1.0),
+                ..ToneMapping::default()
+            },
+        };
+        save_as(&aov.to_display(buffer), aov_path, format, &aov_tone_mapping)?;
+    }
+
+    Ok(())
+}
+
+// writes the snapshots of a progressive render over the output file and passes everything
+// else on to `inner`. a failed write cancels the render, `finish` returns its error.
+pub struct SnapshotWriter<'a> {
+    inner: &'a dyn ProgressObserver,
+    path: PathBuf,
+    format: OutputFormat,
+    tone_mapping: ToneMapping,
+    cancel: CancelToken,
+    error: Mutex<Option<Error>>,
+}
+
+impl<'a> SnapshotWriter<'a> {
+    pub fn new<P: AsRef<Path>>(
+        inner: &'a dyn ProgressObserver,
+        path: P,
+        format: OutputFormat,
+        tone_mapping: &ToneMapping,
+        cancel: &CancelToken,
+    ) -> SnapshotWriter<'a> {
+        SnapshotWriter {
+            inner,
+            path: path.as_ref().to_path_buf(),
+            format,
+            tone_mapping: *tone_mapping,
+            cancel: cancel.clone(),
+            error: Mutex::new(None),
+        }
+    }
+
+    pub fn finish(self) -> Result<(), Error> {
+        match self.error.into_inner().unwrap() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'a> ProgressObserver for SnapshotWriter<'a> {
+    fn on_progress(&self, progress: &Progress) {
+        self.inner.on_progress(progress);
+    }
+
+    fn on_checkpoint(&self, acc: &Accumulator, progress: &Progress) {
+        self.inner.on_checkpoint(acc, progress);
+    }
+
+    fn on_snapshot(&self, fb: &Framebuffer, aovs: &Aovs, progress: &Progress) {
+        self.inner.on_snapshot(fb, aovs, progress);
+
+        let result = save_with_aovs(fb, aovs, &self.path, self.format, &self.tone_mapping);
+        if let Err(e) = result {
+            self.error.lock().unwrap().get_or_insert(e);
+            self.cancel.cancel();
+        }
+    }
+
+    fn on_finish(&self, progress: &Progress, cancelled: bool) {
+        self.inner.on_finish(progress, cancelled);
+    }
+}
+
+// out.png -> out.depth.png
+pub fn aov_path(path: &Path, aov: Aov) -> PathBuf {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    let name = match path.extension().and_then(|s| s.to_str()) {
+        Some(ext) => format!("{}.{}.{}", stem, aov.name(), ext),
+        None => format!("{}.{}", stem, aov.name()),
+    };
+    path.with_file_name(name)
+}
+
+fn channel(fb: &Framebuffer, index: usize) -> Vec<f32> {
+    fb.pixels()
+        .iter()
+        .map(|p| match index {
+            0 => p.x as f32,
+            1 => p.y as f32,
+            _ => p.z as f32,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn from_path() {
+        assert_eq!(
+            OutputFormat::from_path(Path::new("out.JPG")).unwrap(),
+            OutputFormat::Jpeg
+        );
+        assert_eq!(
+            OutputFormat::from_path(Path::new("dir/out.png")).unwrap(),
+            OutputFormat::Png
+        );
+
+        assert!(OutputFormat::from_path(Path::new("out.exr"))
+            .unwrap()
+            .is_hdr());
+
+        match OutputFormat::from_path(Path::new("out.xyz")) {
+            Err(Error::UnsupportedFormat(ext)) => assert_eq!(ext, "xyz"),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn save_errors() {
+        let fb = Framebuffer::new(2, 2);
+        let dir = std::env::temp_dir().join("ray_tracer_output_test");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // the format is checked before the file is created
+        let path = dir.join("out.xyz");
+        match save(&fb, &path, &ToneMapping::default()) {
+            Err(Error::UnsupportedFormat(ext)) => assert_eq!(ext, "xyz"),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(!path.exists());
+
+        match save(&fb, dir.join("missing/out.png"), &ToneMapping::default()) {
+            Err(Error::Io(_)) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn aov_paths() {
+        assert_eq!(
+            aov_path(Path::new("renders/out.png"), Aov::Depth),
+            Path::new("renders/out.depth.png")
+        );
+        assert_eq!(
+            aov_path(Path::new("out"), Aov::ObjectId),
+            Path::new("out.object_id")
+        );
+    }
+}
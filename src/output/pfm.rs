@@ -0,0 +1,44 @@
+// portable float map writer, little endian rgb rows from the bottom up
+
+use crate::framebuffer::Framebuffer;
+
+use std::io::{self, Write};
+
+pub fn write<W: Write>(w: &mut W, fb: &Framebuffer) -> io::Result<()> {
+    // a negative scale marks little endian data
+    write!(w, "PF\n{} {}\n-1.0\n", fb.width(), fb.height())?;
+
+    let mut row = Vec::with_capacity(12 * fb.width() as usize);
+    for y in (0..fb.height()).rev() {
+        row.clear();
+        for x in 0..fb.width() {
+            let col = fb.get(x, y);
+            row.extend_from_slice(&(col.r() as f32).to_le_bytes());
+            row.extend_from_slice(&(col.g() as f32).to_le_bytes());
+            row.extend_from_slice(&(col.b() as f32).to_le_bytes());
+        }
+        w.write_all(&row)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::vec3::Vec3;
+
+    #[test]
+    fn bottom_row_first() {
+        let mut fb = Framebuffer::new(2, 2);
+        fb.set(0, 1, Vec3::new(4.5, 0.0, 0.0));
+
+        let mut out = Vec::new();
+        write(&mut out, &fb).unwrap();
+
+        let header = b"PF\n2 2\n-1.0\n";
+        assert!(out.starts_with(header));
+        assert_eq!(out.len(), header.len() + 2 * 2 * 12);
+        assert_eq!(&out[header.len()..header.len() + 4], &4.5f32.to_le_bytes());
+    }
+}
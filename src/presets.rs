@@ -0,0 +1,157 @@
+// the scenes from the examples, for the command line renderer
+
+use crate::materials::{dielectric::Dielectric, lambertian::Lambertian, metal::Metal, Material};
+use crate::reflexible::sphere::Sphere;
+use crate::reflexible::{Reflexible, ReflexibleList};
+use crate::scene::{CameraParams, Scene};
+use crate::settings::RenderSettings;
+use crate::vec3::Vec3;
+
+use rand::Rng;
+
+pub const NAMES: [&str; 4] = ["random", "lambertian", "metal", "dielectric"];
+
+pub fn preset<R: Rng>(name: &str, rng: &mut R, width: u32, height: u32) -> Option<Scene> {
+    let (list, look_from, look_at) = match name {
+        "random" => (
+            random_spheres(rng),
+            Vec3::new(0.0, 0.5, 7.0),
+            Vec3::new(0.0, 0.0, -1.0),
+        ),
+        "lambertian" => (
+            three_spheres(Material::Lambertian(Lambertian::new(Vec3::new(
+                0.9, 0.2, 0.2,
+            )))),
+            Vec3::new(0.0, 3.0, 7.0),
+            Vec3::new(0.0, 1.5, -1.0),
+        ),
+        "metal" => (
+            three_spheres(Material::Metal(Metal::new(Vec3::new(0.3, 1.0, 0.9), 0.0))),
+            Vec3::new(0.0, 3.0, 7.0),
+            Vec3::new(0.0, 1.5, -1.0),
+        ),
+        "dielectric" => (
+            three_spheres(Material::Dielectric(Dielectric::new(2.5))),
+            Vec3::new(0.0, 3.0, 7.0),
+            Vec3::new(0.0, 1.5, -1.0),
+        ),
+        _ => return None,
+    };
+
+    let camera_params = CameraParams {
+        look_from,
+        look_at,
+        vup: Vec3::new(0.0, 1.0, 0.0),
+        vfov: 30.0,
+        aperture: 0.0,
+        focus_dist: (look_from - look_at).length(),
+        shutter_open: 0.0,
+        shutter_close: 0.0,
+    };
+
+    Some(Scene::new(
+        ReflexibleList::new(list),
+        camera_params,
+        RenderSettings::default(),
+        width,
+        height,
+    ))
+}
+
+pub fn random_spheres<R: Rng>(rng: &mut R) -> Vec<Box<dyn Reflexible>> {
+    let sphere_ground = Sphere::new(
+        Vec3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Material::Lambertian(Lambertian::new(Vec3::new(0.4, 0.6, 0.4))),
+    );
+
+    let mut list: Vec<Box<dyn Reflexible>> = vec![Box::new(sphere_ground)];
+
+    for a in -11..11 {
+        for b in -11..11 {
+            let choose_mat: f64 = rng.gen();
+
+            let center = Vec3::new(
+                f64::from(a) + 0.9 * rng.gen::<f64>(),
+                0.2,
+                f64::from(b) + 0.9 * rng.gen::<f64>(),
+            );
+
+            if (center - Vec3::new(4., 0.2, 0.)).length() > 0.9 {
+                //diffuse
+                if choose_mat < 0.5 {
+                    list.push(Box::new(Sphere::new(
+                        center,
+                        0.2,
+                        Material::Lambertian(Lambertian::new(Vec3::new(
+                            rng.gen::<f64>() * rng.gen::<f64>(),
+                            rng.gen::<f64>() * rng.gen::<f64>(),
+                            rng.gen::<f64>() * rng.gen::<f64>(),
+                        ))),
+                    )));
+                //metal
+                } else if choose_mat < 0.95 {
+                    list.push(Box::new(Sphere::new(
+                        center,
+                        0.2,
+                        Material::Metal(Metal::new(
+                            Vec3::new(
+                                0.5 * (1.0 + rng.gen::<f64>()),
+                                0.5 * (1.0 + rng.gen::<f64>()),
+                                0.5 * (1.0 + rng.gen::<f64>()),
+                            ),
+                            0.1 * rng.gen::<f64>(),
+                        )),
+                    )));
+                } else {
+                    // dielectric
+                    list.push(Box::new(Sphere::new(
+                        center,
+                        0.2,
+                        Material::Dielectric(Dielectric::new(1.5)),
+                    )));
+                }
+            }
+        }
+    }
+
+    list.push(Box::new(Sphere::new(
+        Vec3::new(-4., 5., -7.),
+        5.0,
+        Material::Metal(Metal::new(Vec3::new(0.4, 0.2, 0.1), 0.0)),
+    )));
+    list.push(Box::new(Sphere::new(
+        Vec3::new(5.9, 5., -9.),
+        5.0,
+        Material::Dielectric(Dielectric::new(1.5)),
+    )));
+
+    list
+}
+
+// a ground sphere with three small spheres around a bigger one made of `center_material`
+fn three_spheres(center_material: Material) -> Vec<Box<dyn Reflexible>> {
+    vec![
+        Box::new(Sphere::new(Vec3::new(0.0, 1.0, -1.0), 1.0, center_material)),
+        Box::new(Sphere::new(
+            Vec3::new(0.0, -100.0, -1.0),
+            100.0,
+            Material::Lambertian(Lambertian::new(Vec3::new(0.3, 0.6, 0.7))),
+        )),
+        Box::new(Sphere::new(
+            Vec3::new(2.0, 0.5, 0.3),
+            0.5,
+            Material::Metal(Metal::new(Vec3::new(0.8, 0.6, 0.2), 0.0)),
+        )),
+        Box::new(Sphere::new(
+            Vec3::new(0.0, 2.5, -2.0),
+            0.5,
+            Material::Lambertian(Lambertian::new(Vec3::new(0.1, 0.9, 0.4))),
+        )),
+        Box::new(Sphere::new(
+            Vec3::new(-2.0, 0.5, 0.3),
+            0.5,
+            Material::Dielectric(Dielectric::new(2.5)),
+        )),
+    ]
+}
@@ -0,0 +1,162 @@
+use crate::accumulator::Accumulator;
+use crate::aov::Aovs;
+use crate::framebuffer::Framebuffer;
+
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Progress {
+    pub tiles_done: usize,
+    pub tiles_total: usize,
+    pub rays: u64,
+    // camera samples, fewer than pixels * samples per pixel with adaptive sampling
+    pub samples: u64,
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    pub fn fraction(&self) -> f64 {
+        if self.tiles_total == 0 {
+            1.0
+        } else {
+            self.tiles_done as f64 / self.tiles_total as f64
+        }
+    }
+
+    // linear extrapolation from the tiles finished so far
+    pub fn eta(&self) -> Option<Duration> {
+        if self.tiles_done == 0 {
+            return None;
+        }
+        let remaining = (self.tiles_total - self.tiles_done) as f64;
+        let per_tile = self.elapsed.as_secs_f64() / self.tiles_done as f64;
+        Some(Duration::from_secs_f64(per_tile * remaining))
+    }
+}
+
+// observers are shared between the render threads, so they may be called from any of them.
+// calls are serialized and `tiles_done` never goes backwards, except that `on_checkpoint` is
+// called outside the render's lock and may run alongside the others (never another checkpoint).
+pub trait ProgressObserver: Sync {
+    fn on_progress(&self, progress: &Progress);
+
+    // the image so far, during progressive renders that ask for snapshots
+    fn on_snapshot(&self, _fb: &Framebuffer, _aovs: &Aovs, _progress: &Progress) {}
+
+    // the samples so far, as often as `RenderSettings::checkpoint_interval` asks and once at
+    // the end
+    fn on_checkpoint(&self, _acc: &Accumulator, _progress: &Progress) {}
+
+    fn on_finish(&self, _progress: &Progress, _cancelled: bool) {}
+}
+
+pub struct NoProgress;
+
+impl ProgressObserver for NoProgress {
+    fn on_progress(&self, _progress: &Progress) {}
+}
+
+// prints a single updating line to stdout, only when the percentage changes
+pub struct ConsoleProgress {
+    last_percent: AtomicUsize,
+}
+
+impl ConsoleProgress {
+    pub fn new() -> ConsoleProgress {
+        ConsoleProgress {
+            last_percent: AtomicUsize::new(usize::MAX),
+        }
+    }
+}
+
+impl Default for ConsoleProgress {
+    fn default() -> ConsoleProgress {
+        ConsoleProgress::new()
+    }
+}
+
+impl ProgressObserver for ConsoleProgress {
+    fn on_progress(&self, progress: &Progress) {
+        let percent = (progress.fraction() * 100.0) as usize;
+        if self.last_percent.swap(percent, Ordering::Relaxed) == percent {
+            return;
+        }
+
+        let eta = progress
+            .eta()
+            .map(|eta| format!("{}s", eta.as_secs()))
+            .unwrap_or_else(|| "?".to_string());
+        print!(
+            "\rprogress ==> {}/{} tiles = %{}, eta {}   ",
+            progress.tiles_done, progress.tiles_total, percent, eta
+        );
+        let _ = io::stdout().flush();
+    }
+
+    fn on_finish(&self, progress: &Progress, cancelled: bool) {
+        println!();
+        println!(
+            "{} samples and {} rays traced in {} milliseconds",
+            progress.samples,
+            progress.rays,
+            progress.elapsed.as_millis()
+        );
+        if cancelled {
+            println!(
+                "render cancelled after {}/{} tiles",
+                progress.tiles_done, progress.tiles_total
+            );
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    pub fn new() -> CancelToken {
+        CancelToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn eta() {
+        let progress = Progress {
+            tiles_done: 1,
+            tiles_total: 4,
+            rays: 0,
+            samples: 0,
+            elapsed: Duration::from_secs(2),
+        };
+
+        assert_eq!(progress.eta(), Some(Duration::from_secs(6)));
+        assert_eq!(progress.fraction(), 0.25);
+    }
+
+    #[test]
+    fn cancel_token_is_shared() {
+        let token = CancelToken::new();
+        let clone = token.clone();
+
+        assert!(!token.is_cancelled());
+        clone.cancel();
+        assert!(token.is_cancelled());
+    }
+}
@@ -4,11 +4,22 @@ use super::vec3::Vec3;
 pub struct Ray {
     pub origin: Vec3,
     pub direction: Vec3,
+    // when the ray was cast, within the camera's shutter interval
+    pub time: f64,
 }
 
 impl Ray {
     pub fn new(origin: Vec3, direction: Vec3) -> Ray {
-        Ray { origin, direction }
+        Ray {
+            origin,
+            direction,
+            time: 0.0,
+        }
+    }
+
+    pub fn with_time(mut self, time: f64) -> Ray {
+        self.time = time;
+        self
     }
 
     pub fn point_at_parameter(&self, t: f64) -> Vec3 {
@@ -25,6 +36,7 @@ mod test {
         let r1 = Ray {
             origin: Vec3::new(1.0, 1.0, 1.0),
             direction: Vec3::new(1.0, 1.0, 1.0),
+            time: 0.0,
         };
         let r2 = Ray::new(Vec3::new(1.0, 1.0, 1.0), Vec3::new(1.0, 1.0, 1.0));
 
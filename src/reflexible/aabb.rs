@@ -0,0 +1,97 @@
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+// flat shapes lying in an axis plane still need boxes with some thickness
+pub const BOX_PADDING: f64 = 1e-4;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub fn new(min: Vec3, max: Vec3) -> Aabb {
+        Aabb { min, max }
+    }
+
+    pub fn surrounding(a: &Aabb, b: &Aabb) -> Aabb {
+        Aabb {
+            min: Vec3::new(
+                f64::min(a.min.x, b.min.x),
+                f64::min(a.min.y, b.min.y),
+                f64::min(a.min.z, b.min.z),
+            ),
+            max: Vec3::new(
+                f64::max(a.max.x, b.max.x),
+                f64::max(a.max.y, b.max.y),
+                f64::max(a.max.z, b.max.z),
+            ),
+        }
+    }
+
+    pub fn centroid(&self) -> Vec3 {
+        0.5 * (self.min + self.max)
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        let d = self.max - self.min;
+        2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
+
+    // slab test, 0 = x, 1 = y, 2 = z
+    pub fn hit(&self, r: &Ray, mut t_min: f64, mut t_max: f64) -> bool {
+        for axis in 0..3 {
+            let inv_d = 1.0 / axis_of(&r.direction, axis);
+            let mut t0 = (axis_of(&self.min, axis) - axis_of(&r.origin, axis)) * inv_d;
+            let mut t1 = (axis_of(&self.max, axis) - axis_of(&r.origin, axis)) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_min = if t0 > t_min { t0 } else { t_min };
+            t_max = if t1 < t_max { t1 } else { t_max };
+            if t_max <= t_min {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+pub fn axis_of(v: &Vec3, axis: usize) -> f64 {
+    match axis {
+        0 => v.x,
+        1 => v.y,
+        _ => v.z,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn hit() {
+        let bbox = Aabb::new(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0));
+
+        let towards = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        let away = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, 1.0));
+        let beside = Ray::new(Vec3::new(2.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+
+        assert!(bbox.hit(&towards, 0.0, f64::MAX));
+        assert!(!bbox.hit(&towards, 0.0, 3.0));
+        assert!(!bbox.hit(&away, 0.0, f64::MAX));
+        assert!(!bbox.hit(&beside, 0.0, f64::MAX));
+    }
+
+    #[test]
+    fn surrounding() {
+        let a = Aabb::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0));
+        let b = Aabb::new(Vec3::new(-1.0, 0.5, 0.0), Vec3::new(0.5, 2.0, 1.0));
+
+        let c = Aabb::surrounding(&a, &b);
+        assert_eq!(c.min, Vec3::new(-1.0, 0.0, 0.0));
+        assert_eq!(c.max, Vec3::new(1.0, 2.0, 1.0));
+        assert_eq!(c.surface_area(), 2.0 * (4.0 + 2.0 + 2.0));
+    }
+}
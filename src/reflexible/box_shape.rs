@@ -0,0 +1,118 @@
+use crate::lights::Light;
+use crate::materials::Material;
+use crate::ray::Ray;
+use crate::reflexible::aabb::Aabb;
+use crate::reflexible::rect::{XyRect, XzRect, YzRect};
+use crate::reflexible::HitRecord;
+use crate::reflexible::{Reflexible, ReflexibleList};
+use crate::vec3::Vec3;
+
+use std::sync::Arc;
+
+// an axis aligned box between two opposite corners, made of six rectangles facing out
+pub struct BoxShape {
+    faces: ReflexibleList,
+}
+
+impl BoxShape {
+    pub fn new<M: Into<Arc<Material>>>(p0: Vec3, p1: Vec3, material: M) -> BoxShape {
+        let material = material.into();
+        let min = Vec3::new(p0.x.min(p1.x), p0.y.min(p1.y), p0.z.min(p1.z));
+        let max = Vec3::new(p0.x.max(p1.x), p0.y.max(p1.y), p0.z.max(p1.z));
+
+        let faces: Vec<Box<dyn Reflexible>> = vec![
+            Box::new(XyRect::new(
+                min.x,
+                max.x,
+                min.y,
+                max.y,
+                max.z,
+                material.clone(),
+            )),
+            Box::new(XyRect::new(min.x, max.x, min.y, max.y, min.z, material.clone()).flipped()),
+            Box::new(XzRect::new(
+                min.x,
+                max.x,
+                min.z,
+                max.z,
+                max.y,
+                material.clone(),
+            )),
+            Box::new(XzRect::new(min.x, max.x, min.z, max.z, min.y, material.clone()).flipped()),
+            Box::new(YzRect::new(
+                min.y,
+                max.y,
+                min.z,
+                max.z,
+                max.x,
+                material.clone(),
+            )),
+            Box::new(YzRect::new(min.y, max.y, min.z, max.z, min.x, material).flipped()),
+        ];
+
+        BoxShape {
+            faces: ReflexibleList::new(faces),
+        }
+    }
+}
+
+impl Reflexible for BoxShape {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        // the faces' index isn't an object of the world
+        self.faces
+            .hit(r, t_min, t_max)
+            .map(|hit| hit.with_object(0))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.faces.bounding_box()
+    }
+
+    fn lights<'a>(&'a self, lights: &mut Vec<Box<dyn Light + 'a>>) {
+        self.faces.lights(lights)
+    }
+
+    fn materials<'a>(&'a self, materials: &mut Vec<&'a Material>) {
+        self.faces.materials(materials)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::lambertian::Lambertian;
+
+    #[test]
+    fn normals_face_out() {
+        let cube = BoxShape::new(
+            Vec3::new(1.0, 1.0, 1.0),
+            Vec3::new(-1.0, -1.0, -1.0),
+            Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+        );
+        let bbox = cube.bounding_box().unwrap();
+        assert!((bbox.min - Vec3::new(-1.0, -1.0, -1.0)).length() < 1e-3);
+        assert!((bbox.max - Vec3::new(1.0, 1.0, 1.0)).length() < 1e-3);
+
+        let directions = [
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(-1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, -1.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+            Vec3::new(0.0, 0.0, -1.0),
+        ];
+        for d in directions.iter() {
+            let r = Ray::new(5.0 * *d, -*d);
+            let rec = cube.hit(&r, 0.001, f64::MAX).unwrap();
+            assert_eq!(rec.normal, *d);
+            assert!(rec.front_face);
+            assert!((rec.t - 4.0).abs() < 1e-12);
+
+            // from the inside the far face is hit from behind
+            let inside = Ray::new(Vec3::new(0.0, 0.0, 0.0), *d);
+            let rec = cube.hit(&inside, 0.001, f64::MAX).unwrap();
+            assert_eq!(rec.normal, -*d);
+            assert!(!rec.front_face);
+        }
+    }
+}
@@ -0,0 +1,369 @@
+use crate::lights::Light;
+use crate::materials::Material;
+use crate::ray::Ray;
+use crate::reflexible::aabb::{axis_of, Aabb};
+use crate::reflexible::{HitRecord, Reflexible, ReflexibleList};
+
+const MAX_LEAF_SIZE: usize = 4;
+const BUCKETS: usize = 12;
+// cost of one node traversal relative to one primitive intersection
+const TRAVERSAL_COST: f64 = 0.125;
+
+enum BvhNode {
+    Leaf {
+        bbox: Aabb,
+        objects: Vec<(usize, Box<dyn Reflexible>)>,
+    },
+    Interior {
+        bbox: Aabb,
+        left: Box<BvhNode>,
+        right: Box<BvhNode>,
+    },
+}
+
+// bounding volume hierarchy built with the binned surface area heuristic.
+// objects without a bounding box (infinite planes) are kept aside and tested linearly.
+// objects keep their index in the original list, which hits report like `ReflexibleList`'s.
+pub struct Bvh {
+    root: Option<BvhNode>,
+    unbounded: Vec<(usize, Box<dyn Reflexible>)>,
+}
+
+impl Bvh {
+    pub fn new(list: Vec<Box<dyn Reflexible>>) -> Bvh {
+        let mut bounded = Vec::new();
+        let mut unbounded = Vec::new();
+
+        for (index, object) in list.into_iter().enumerate() {
+            match object.bounding_box() {
+                Some(bbox) => bounded.push((bbox, index, object)),
+                None => unbounded.push((index, object)),
+            }
+        }
+
+        let root = if bounded.is_empty() {
+            None
+        } else {
+            Some(build(bounded))
+        };
+
+        Bvh { root, unbounded }
+    }
+}
+
+impl From<ReflexibleList> for Bvh {
+    fn from(list: ReflexibleList) -> Bvh {
+        Bvh::new(list.list)
+    }
+}
+
+impl Reflexible for Bvh {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let mut hit_record = None;
+        let mut closest_so_far = t_max;
+
+        if let Some(ref root) = self.root {
+            if let Some(hit) = root.hit(r, t_min, closest_so_far) {
+                hit_record = Some(hit);
+                closest_so_far = hit.t;
+            }
+        }
+        for (index, object) in &self.unbounded {
+            if let Some(hit) = object.hit(r, t_min, closest_so_far) {
+                hit_record = Some(hit.with_object(index + 1));
+                closest_so_far = hit.t;
+            }
+        }
+
+        hit_record
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        match self.root {
+            Some(ref root) if self.unbounded.is_empty() => Some(*root.bbox()),
+            _ => None,
+        }
+    }
+
+    fn lights<'a>(&'a self, lights: &mut Vec<Box<dyn Light + 'a>>) {
+        if let Some(ref root) = self.root {
+            root.lights(lights);
+        }
+        for (_, object) in &self.unbounded {
+            object.lights(lights);
+        }
+    }
+
+    // in the order of the original list, whatever shape the tree took
+    fn materials<'a>(&'a self, materials: &mut Vec<&'a Material>) {
+        let mut objects: Vec<&(usize, Box<dyn Reflexible>)> = self.unbounded.iter().collect();
+        if let Some(ref root) = self.root {
+            root.objects(&mut objects);
+        }
+        objects.sort_by_key(|object| object.0);
+        for (_, object) in objects {
+            object.materials(materials);
+        }
+    }
+}
+
+impl BvhNode {
+    fn bbox(&self) -> &Aabb {
+        match *self {
+            BvhNode::Leaf { ref bbox, .. } => bbox,
+            BvhNode::Interior { ref bbox, .. } => bbox,
+        }
+    }
+
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        if !self.bbox().hit(r, t_min, t_max) {
+            return None;
+        }
+
+        match *self {
+            BvhNode::Leaf { ref objects, .. } => {
+                let mut hit_record = None;
+                let mut closest_so_far = t_max;
+                for (index, object) in objects {
+                    if let Some(hit) = object.hit(r, t_min, closest_so_far) {
+                        hit_record = Some(hit.with_object(index + 1));
+                        closest_so_far = hit.t;
+                    }
+                }
+                hit_record
+            }
+            BvhNode::Interior {
+                ref left,
+                ref right,
+                ..
+            } => {
+                let left_hit = left.hit(r, t_min, t_max);
+                let closest_so_far = left_hit.map_or(t_max, |hit| hit.t);
+                right.hit(r, t_min, closest_so_far).or(left_hit)
+            }
+        }
+    }
+
+    fn lights<'a>(&'a self, lights: &mut Vec<Box<dyn Light + 'a>>) {
+        match *self {
+            BvhNode::Leaf { ref objects, .. } => {
+                for (_, object) in objects {
+                    object.lights(lights);
+                }
+            }
+            BvhNode::Interior {
+                ref left,
+                ref right,
+                ..
+            } => {
+                left.lights(lights);
+                right.lights(lights);
+            }
+        }
+    }
+
+    fn objects<'a>(&'a self, objects: &mut Vec<&'a (usize, Box<dyn Reflexible>)>) {
+        match *self {
+            BvhNode::Leaf {
+                objects: ref leaf, ..
+            } => objects.extend(leaf.iter()),
+            BvhNode::Interior {
+                ref left,
+                ref right,
+                ..
+            } => {
+                left.objects(objects);
+                right.objects(objects);
+            }
+        }
+    }
+}
+
+fn build(mut items: Vec<(Aabb, usize, Box<dyn Reflexible>)>) -> BvhNode {
+    let bbox = items
+        .iter()
+        .skip(1)
+        .fold(items[0].0, |acc, item| Aabb::surrounding(&acc, &item.0));
+
+    if items.len() <= 2 {
+        return leaf(bbox, items);
+    }
+
+    let centroid_bounds = items.iter().skip(1).fold(
+        Aabb::new(items[0].0.centroid(), items[0].0.centroid()),
+        |acc, item| {
+            let c = item.0.centroid();
+            Aabb::surrounding(&acc, &Aabb::new(c, c))
+        },
+    );
+    let extent = centroid_bounds.max - centroid_bounds.min;
+    let axis = if extent.x > extent.y && extent.x > extent.z {
+        0
+    } else if extent.y > extent.z {
+        1
+    } else {
+        2
+    };
+    let axis_min = axis_of(&centroid_bounds.min, axis);
+    let axis_extent = axis_of(&extent, axis);
+
+    // every centroid in the same spot, the heuristic can't separate them
+    if axis_extent <= 0.0 {
+        return split_at_median(bbox, items, axis);
+    }
+
+    let bucket_of = |bbox: &Aabb| {
+        let offset = (axis_of(&bbox.centroid(), axis) - axis_min) / axis_extent;
+        usize::min((offset * BUCKETS as f64) as usize, BUCKETS - 1)
+    };
+
+    let mut counts = [0usize; BUCKETS];
+    let mut bounds: [Option<Aabb>; BUCKETS] = [None; BUCKETS];
+    for item in &items {
+        let b = bucket_of(&item.0);
+        counts[b] += 1;
+        bounds[b] = Some(match bounds[b] {
+            Some(ref acc) => Aabb::surrounding(acc, &item.0),
+            None => item.0,
+        });
+    }
+
+    let mut best_cost = f64::MAX;
+    let mut best_split = 0;
+    for split in 0..BUCKETS - 1 {
+        let (left_count, left_area) = merge_buckets(&counts[..=split], &bounds[..=split]);
+        let (right_count, right_area) = merge_buckets(&counts[split + 1..], &bounds[split + 1..]);
+        if left_count == 0 || right_count == 0 {
+            continue;
+        }
+
+        let cost = TRAVERSAL_COST
+            + (left_count as f64 * left_area + right_count as f64 * right_area)
+                / bbox.surface_area();
+        if cost < best_cost {
+            best_cost = cost;
+            best_split = split;
+        }
+    }
+
+    if items.len() <= MAX_LEAF_SIZE && best_cost >= items.len() as f64 {
+        return leaf(bbox, items);
+    }
+    if best_cost == f64::MAX {
+        return split_at_median(bbox, items, axis);
+    }
+
+    let (left, right): (Vec<_>, Vec<_>) = items
+        .drain(..)
+        .partition(|item| bucket_of(&item.0) <= best_split);
+
+    BvhNode::Interior {
+        bbox,
+        left: Box::new(build(left)),
+        right: Box::new(build(right)),
+    }
+}
+
+fn merge_buckets(counts: &[usize], bounds: &[Option<Aabb>]) -> (usize, f64) {
+    let count = counts.iter().sum();
+    let bbox = bounds
+        .iter()
+        .fold(None, |acc: Option<Aabb>, b| match (acc, b) {
+            (Some(acc), Some(b)) => Some(Aabb::surrounding(&acc, b)),
+            (None, Some(b)) => Some(*b),
+            (acc, None) => acc,
+        });
+
+    (count, bbox.map_or(0.0, |b| b.surface_area()))
+}
+
+fn split_at_median(
+    bbox: Aabb,
+    mut items: Vec<(Aabb, usize, Box<dyn Reflexible>)>,
+    axis: usize,
+) -> BvhNode {
+    if items.len() <= MAX_LEAF_SIZE {
+        return leaf(bbox, items);
+    }
+
+    items.sort_by(|a, b| {
+        axis_of(&a.0.centroid(), axis)
+            .partial_cmp(&axis_of(&b.0.centroid(), axis))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    let right = items.split_off(items.len() / 2);
+
+    BvhNode::Interior {
+        bbox,
+        left: Box::new(build(items)),
+        right: Box::new(build(right)),
+    }
+}
+
+fn leaf(bbox: Aabb, items: Vec<(Aabb, usize, Box<dyn Reflexible>)>) -> BvhNode {
+    BvhNode::Leaf {
+        bbox,
+        objects: items.into_iter().map(|item| (item.1, item.2)).collect(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::{lambertian::Lambertian, Material};
+    use crate::reflexible::sphere::Sphere;
+    use crate::vec3::Vec3;
+
+    use rand::{Rng, SeedableRng};
+
+    fn spheres(rng: &mut rand::rngs::StdRng) -> Vec<Box<dyn Reflexible>> {
+        let material = Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)));
+        (0..200)
+            .map(|_| {
+                let center = Vec3::new(
+                    rng.gen_range(-10.0, 10.0),
+                    rng.gen_range(-10.0, 10.0),
+                    rng.gen_range(-10.0, 10.0),
+                );
+                Box::new(Sphere::new(
+                    center,
+                    rng.gen_range(0.1, 1.5),
+                    material.clone(),
+                )) as Box<dyn Reflexible>
+            })
+            .collect()
+    }
+
+    #[test]
+    fn matches_linear_list() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let list = ReflexibleList::new(spheres(&mut rng));
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let bvh = Bvh::new(spheres(&mut rng));
+
+        for _ in 0..1000 {
+            let origin = Vec3::new(
+                rng.gen_range(-15.0, 15.0),
+                rng.gen_range(-15.0, 15.0),
+                rng.gen_range(-15.0, 15.0),
+            );
+            let direction = Vec3::new(
+                rng.gen_range(-1.0, 1.0),
+                rng.gen_range(-1.0, 1.0),
+                rng.gen_range(-1.0, 1.0),
+            );
+            let r = Ray::new(origin, direction);
+
+            assert_eq!(list.hit(&r, 0.001, f64::MAX), bvh.hit(&r, 0.001, f64::MAX));
+        }
+    }
+
+    #[test]
+    fn bounding_box() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+        let list = ReflexibleList::new(spheres(&mut rng));
+        let expected = list.bounding_box();
+
+        assert_eq!(Bvh::from(list).bounding_box(), expected);
+    }
+}
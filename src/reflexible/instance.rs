@@ -0,0 +1,220 @@
+use crate::lights::Light;
+use crate::materials::Material;
+use crate::ray::Ray;
+use crate::reflexible::aabb::Aabb;
+use crate::reflexible::HitRecord;
+use crate::reflexible::Reflexible;
+use crate::sampler::Sampler;
+use crate::transform::{AnimatedTransform, Transform};
+use crate::vec3::Vec3;
+
+use std::sync::Arc;
+
+// an object placed in the world by a transform, which can move with the ray's time. the
+// object is shared, so the same mesh can be instanced many times without copying it.
+pub struct Instance {
+    object: Arc<dyn Reflexible>,
+    transform: AnimatedTransform,
+    bbox: Option<Aabb>,
+}
+
+impl Instance {
+    pub fn new(object: Arc<dyn Reflexible>, transform: Transform) -> Instance {
+        Instance::animated(object, AnimatedTransform::fixed(transform))
+    }
+
+    pub fn animated(object: Arc<dyn Reflexible>, transform: AnimatedTransform) -> Instance {
+        let bbox = object
+            .bounding_box()
+            .map(|bbox| transform.bounding_box(&bbox));
+
+        Instance {
+            object,
+            transform,
+            bbox,
+        }
+    }
+
+    pub fn transform(&self) -> &AnimatedTransform {
+        &self.transform
+    }
+}
+
+impl Reflexible for Instance {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let transform = self.transform.at(r.time);
+        // the direction isn't normalized, so t is the same in both spaces
+        let inverse = transform.inverse();
+        let local = Ray::new(
+            inverse.transform_point(r.origin),
+            inverse.transform_vector(r.direction),
+        )
+        .with_time(r.time);
+        let mut rec = self.object.hit(&local, t_min, t_max)?;
+
+        rec.p = r.point_at_parameter(rec.t);
+        rec.normal = Vec3::unit_vector(transform.normal(rec.normal));
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.bbox
+    }
+
+    // lights are sampled without a time, so moving ones are left to bounced rays, and so
+    // are stretched ones whose densities don't carry over
+    fn lights<'a>(&'a self, lights: &mut Vec<Box<dyn Light + 'a>>) {
+        let transform = self.transform.at(0.0);
+        if self.transform.is_animated() || !transform.is_similarity() {
+            return;
+        }
+
+        let mut inner = Vec::new();
+        self.object.lights(&mut inner);
+        for light in inner {
+            lights.push(Box::new(TransformedLight { light, transform }));
+        }
+    }
+
+    fn materials<'a>(&'a self, materials: &mut Vec<&'a Material>) {
+        self.object.materials(materials)
+    }
+}
+
+// a light of the instanced object, sampled in its space. similarities keep solid angles,
+// so the densities are the same in both.
+struct TransformedLight<'a> {
+    light: Box<dyn Light + 'a>,
+    transform: Transform,
+}
+
+impl<'a> Light for TransformedLight<'a> {
+    fn sample(&self, origin: Vec3, sampler: &mut Sampler) -> Option<(Vec3, f64)> {
+        let origin = self.transform.inverse().transform_point(origin);
+        let (direction, pdf) = self.light.sample(origin, sampler)?;
+        Some((self.transform.vector(direction), pdf))
+    }
+
+    fn pdf(&self, origin: Vec3, direction: Vec3) -> f64 {
+        let inverse = self.transform.inverse();
+        self.light.pdf(
+            inverse.transform_point(origin),
+            inverse.transform_vector(direction),
+        )
+    }
+
+    fn material(&self) -> &Material {
+        self.light.material()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lights::LightList;
+    use crate::materials::diffuse_light::DiffuseLight;
+    use crate::materials::lambertian::Lambertian;
+    use crate::reflexible::sphere::Sphere;
+
+    #[test]
+    fn hit() {
+        let sphere: Arc<dyn Reflexible> = Arc::new(Sphere::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            1.0,
+            Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+        ));
+        // an ellipsoid twice as wide as it is high, moved up
+        let transform = Transform::scale(Vec3::new(2.0, 1.0, 1.0))
+            .unwrap()
+            .then(&Transform::translate(Vec3::new(0.0, 3.0, 0.0)));
+        let instance = Instance::new(sphere.clone(), transform);
+        let other = Instance::new(sphere, Transform::identity());
+
+        let r = Ray::new(Vec3::new(10.0, 3.0, 0.0), Vec3::new(-1.0, 0.0, 0.0));
+        let rec = instance.hit(&r, 0.001, f64::MAX).unwrap();
+        assert!((rec.t - 8.0).abs() < 1e-12);
+        assert!((rec.p - Vec3::new(2.0, 3.0, 0.0)).length() < 1e-12);
+        assert!((rec.normal - Vec3::new(1.0, 0.0, 0.0)).length() < 1e-12);
+        assert!(other.hit(&r, 0.001, f64::MAX).is_none());
+
+        let bbox = instance.bounding_box().unwrap();
+        assert!((bbox.min - Vec3::new(-2.0, 2.0, -1.0)).length() < 1e-12);
+        assert!((bbox.max - Vec3::new(2.0, 4.0, 1.0)).length() < 1e-12);
+    }
+
+    #[test]
+    fn moving() {
+        let sphere: Arc<dyn Reflexible> = Arc::new(Sphere::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            1.0,
+            Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+        ));
+        let instance = Instance::animated(
+            sphere,
+            AnimatedTransform::new(
+                Transform::identity(),
+                Transform::translate(Vec3::new(0.0, 4.0, 0.0)),
+                0.0,
+                1.0,
+            ),
+        );
+
+        let r = Ray::new(Vec3::new(0.0, 2.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(instance.hit(&r, 0.001, f64::MAX).is_none());
+        let rec = instance.hit(&r.with_time(0.5), 0.001, f64::MAX).unwrap();
+        assert!((rec.t - 4.0).abs() < 1e-12);
+
+        let bbox = instance.bounding_box().unwrap();
+        assert!((bbox.max - Vec3::new(1.0, 5.0, 1.0)).length() < 1e-12);
+    }
+
+    #[test]
+    fn lights() {
+        let sphere: Arc<dyn Reflexible> = Arc::new(Sphere::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            1.0,
+            Material::DiffuseLight(DiffuseLight::new(Vec3::new(4.0, 4.0, 4.0))),
+        ));
+        let moved = Instance::new(
+            sphere.clone(),
+            Transform::translate(Vec3::new(0.0, 5.0, 0.0)),
+        );
+        let lights = LightList::new(&moved);
+        assert_eq!(lights.len(), 1);
+
+        // uniform in the cone the sphere covers from 5 units below
+        let mut sampler = Sampler::new(3);
+        let (light, _) = lights.pick(&mut sampler).unwrap();
+        let origin = Vec3::new(0.0, 0.0, 0.0);
+        let cone = 1.0 - f64::sqrt(1.0 - 1.0 / 25.0);
+        let expected = 1.0 / (2.0 * std::f64::consts::PI * cone);
+        for _ in 0..16 {
+            let (direction, pdf) = light.sample(origin, &mut sampler).unwrap();
+            assert!((pdf - expected).abs() < 1e-9 * expected);
+            assert!(moved
+                .hit(&Ray::new(origin, direction), 0.001, f64::MAX)
+                .is_some());
+            assert!((light.pdf(origin, direction) - expected).abs() < 1e-9 * expected);
+        }
+        assert_eq!(light.pdf(origin, Vec3::new(0.0, -1.0, 0.0)), 0.0);
+        let r = Ray::new(origin, Vec3::new(0.0, 1.0, 0.0));
+        assert!(lights.find(light.material(), &r).is_some());
+
+        // stretched or moving lights aren't sampled
+        let stretched = Instance::new(
+            sphere.clone(),
+            Transform::scale(Vec3::new(1.0, 2.0, 1.0)).unwrap(),
+        );
+        assert!(LightList::new(&stretched).is_empty());
+        let moving = Instance::animated(
+            sphere,
+            AnimatedTransform::new(
+                Transform::identity(),
+                Transform::translate(Vec3::new(0.0, 4.0, 0.0)),
+                0.0,
+                1.0,
+            ),
+        );
+        assert!(LightList::new(&moving).is_empty());
+    }
+}
@@ -0,0 +1,218 @@
+use crate::error::Error;
+use crate::materials::Material;
+use crate::ray::Ray;
+use crate::reflexible::aabb::Aabb;
+use crate::reflexible::bvh::Bvh;
+use crate::reflexible::triangle;
+use crate::reflexible::HitRecord;
+use crate::reflexible::Reflexible;
+use crate::vec3::Vec3;
+
+use std::sync::Arc;
+
+struct MeshData {
+    positions: Vec<Vec3>,
+    normals: Option<Vec<Vec3>>,
+    uvs: Option<Vec<(f64, f64)>>,
+    material: Arc<Material>,
+}
+
+// a single face of a mesh, referring back to the shared vertex data
+struct MeshTriangle {
+    mesh: Arc<MeshData>,
+    indices: [usize; 3],
+}
+
+// indexed triangle mesh, normals and uvs (when given) are indexed like the positions.
+// the faces are kept in their own bvh.
+pub struct TriangleMesh {
+    faces: Bvh,
+    triangle_count: usize,
+    material: Arc<Material>,
+}
+
+impl TriangleMesh {
+    pub fn new<M: Into<Arc<Material>>>(
+        positions: Vec<Vec3>,
+        normals: Option<Vec<Vec3>>,
+        uvs: Option<Vec<(f64, f64)>>,
+        indices: Vec<[usize; 3]>,
+        material: M,
+    ) -> Result<TriangleMesh, Error> {
+        if let Some(ref normals) = normals {
+            if normals.len() != positions.len() {
+                return Err(Error::InvalidMesh(format!(
+                    "{} normals for {} vertices",
+                    normals.len(),
+                    positions.len()
+                )));
+            }
+        }
+        if let Some(ref uvs) = uvs {
+            if uvs.len() != positions.len() {
+                return Err(Error::InvalidMesh(format!(
+                    "{} uvs for {} vertices",
+                    uvs.len(),
+                    positions.len()
+                )));
+            }
+        }
+        for (n, face) in indices.iter().enumerate() {
+            if let Some(&i) = face.iter().find(|&&i| i >= positions.len()) {
+                return Err(Error::InvalidMesh(format!(
+                    "face {} uses vertex {}, there are {}",
+                    n,
+                    i,
+                    positions.len()
+                )));
+            }
+        }
+
+        let mesh = Arc::new(MeshData {
+            positions,
+            normals,
+            uvs,
+            material: material.into(),
+        });
+        let material = mesh.material.clone();
+        let triangle_count = indices.len();
+        let faces = indices
+            .into_iter()
+            .map(|indices| {
+                Box::new(MeshTriangle {
+                    mesh: mesh.clone(),
+                    indices,
+                }) as Box<dyn Reflexible>
+            })
+            .collect();
+
+        Ok(TriangleMesh {
+            faces: Bvh::new(faces),
+            triangle_count,
+            material,
+        })
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.triangle_count
+    }
+}
+
+impl Reflexible for TriangleMesh {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        self.faces.hit(r, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.faces.bounding_box()
+    }
+
+    // the faces all share it
+    fn materials<'a>(&'a self, materials: &mut Vec<&'a Material>) {
+        materials.push(&self.material);
+    }
+}
+
+impl MeshTriangle {
+    fn vertices(&self) -> [Vec3; 3] {
+        let [i0, i1, i2] = self.indices;
+        let p = &self.mesh.positions;
+        [p[i0], p[i1], p[i2]]
+    }
+}
+
+impl Reflexible for MeshTriangle {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let vertices = self.vertices();
+        let (t, b1, b2) =
+            triangle::intersect(vertices[0], vertices[1], vertices[2], r, t_min, t_max)?;
+
+        let [i0, i1, i2] = self.indices;
+        let normals = self.mesh.normals.as_ref().map(|n| [n[i0], n[i1], n[i2]]);
+        let uvs = self.mesh.uvs.as_ref().map(|uv| [uv[i0], uv[i1], uv[i2]]);
+
+        Some(triangle::shade(
+            r,
+            t,
+            b1,
+            b2,
+            &vertices,
+            normals.as_ref(),
+            uvs.as_ref(),
+            &self.mesh.material,
+        ))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let [v0, v1, v2] = self.vertices();
+        Some(triangle::bounds(v0, v1, v2))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::lambertian::Lambertian;
+
+    #[test]
+    fn quad() {
+        let mesh = TriangleMesh::new(
+            vec![
+                Vec3::new(-1.0, -1.0, 0.0),
+                Vec3::new(1.0, -1.0, 0.0),
+                Vec3::new(1.0, 1.0, 0.0),
+                Vec3::new(-1.0, 1.0, 0.0),
+            ],
+            None,
+            Some(vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]),
+            vec![[0, 1, 2], [0, 2, 3]],
+            Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+        )
+        .unwrap();
+
+        assert_eq!(mesh.triangle_count(), 2);
+
+        let r = Ray::new(Vec3::new(-0.5, 0.5, 3.0), Vec3::new(0.0, 0.0, -1.0));
+        let rec = mesh.hit(&r, 0.001, f64::MAX).unwrap();
+        assert!((rec.t - 3.0).abs() < 1e-12);
+        assert!((rec.u - 0.25).abs() < 1e-12 && (rec.v - 0.75).abs() < 1e-12);
+
+        let r = Ray::new(Vec3::new(1.5, 0.5, 3.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(mesh.hit(&r, 0.001, f64::MAX).is_none());
+    }
+
+    #[test]
+    fn invalid() {
+        let material = || Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)));
+        let positions = || {
+            vec![
+                Vec3::new(0.0, 0.0, 0.0),
+                Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+            ]
+        };
+        let message = |result: Result<TriangleMesh, Error>| match result {
+            Err(Error::InvalidMesh(message)) => message,
+            Err(e) => panic!("unexpected {}", e),
+            Ok(_) => panic!("accepted"),
+        };
+
+        let normals = vec![Vec3::new(0.0, 0.0, 1.0)];
+        let result = TriangleMesh::new(
+            positions(),
+            Some(normals),
+            None,
+            vec![[0, 1, 2]],
+            material(),
+        );
+        assert_eq!(message(result), "1 normals for 3 vertices");
+
+        let uvs = vec![(0.0, 0.0); 4];
+        let result = TriangleMesh::new(positions(), None, Some(uvs), vec![[0, 1, 2]], material());
+        assert_eq!(message(result), "4 uvs for 3 vertices");
+
+        let indices = vec![[0, 1, 2], [1, 2, 3]];
+        let result = TriangleMesh::new(positions(), None, None, indices, material());
+        assert_eq!(message(result), "face 1 uses vertex 3, there are 3");
+    }
+}
@@ -1,27 +1,71 @@
+pub mod aabb;
+pub mod box_shape;
+pub mod bvh;
+pub mod instance;
+pub mod mesh;
+pub mod moving_sphere;
+pub mod plane;
+pub mod rect;
 pub mod sphere;
+pub mod triangle;
 
+use crate::lights::Light;
 use crate::materials::Material;
 use crate::ray::Ray;
+use crate::reflexible::aabb::Aabb;
 use crate::vec3::Vec3;
 
+use std::sync::Arc;
+
 #[derive(Clone, Copy, Debug, PartialEq)]
-pub struct HitRecord {
+pub struct HitRecord<'a> {
     pub t: f64,
     pub p: Vec3,
+    // faces the ray, see `facing`
     pub normal: Vec3,
-    pub material: Material,
+    // whether the ray hit the side the surface's own normal points out of
+    pub front_face: bool,
+    pub u: f64,
+    pub v: f64,
+    pub material: &'a Material,
+    // 1-based index of the hit object in the outermost list or bvh, 0 if there is none
+    pub object: usize,
 }
 
-impl HitRecord {
-    pub fn new(t: f64, p: Vec3, normal: Vec3, material: Material) -> HitRecord {
+impl<'a> HitRecord<'a> {
+    pub fn new(t: f64, p: Vec3, normal: Vec3, material: &'a Material) -> HitRecord<'a> {
         HitRecord {
             t,
             p,
             normal,
+            front_face: true,
+            u: 0.0,
+            v: 0.0,
             material,
+            object: 0,
         }
     }
 
+    // turns the normal against `r`, so that both sides of a surface are shaded alike
+    pub fn facing(mut self, r: &Ray) -> HitRecord<'a> {
+        self.front_face = self.normal.dot(&r.direction) < 0.0;
+        if !self.front_face {
+            self.normal = -self.normal;
+        }
+        self
+    }
+
+    pub fn with_uv(mut self, u: f64, v: f64) -> HitRecord<'a> {
+        self.u = u;
+        self.v = v;
+        self
+    }
+
+    pub fn with_object(mut self, object: usize) -> HitRecord<'a> {
+        self.object = object;
+        self
+    }
+
     //    pub fn zero() -> HitRecord {
     //        HitRecord {
     //            t: 0.0,
@@ -31,8 +75,36 @@ impl HitRecord {
     //    }
 }
 
-pub trait Reflexible {
-    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord>;
+pub trait Reflexible: Send + Sync {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>>;
+
+    // None for unbounded objects
+    fn bounding_box(&self) -> Option<Aabb>;
+
+    // adds the emissive objects that can be sampled directly, see `lights::LightList`
+    fn lights<'a>(&'a self, _lights: &mut Vec<Box<dyn Light + 'a>>) {}
+
+    // adds the materials of its surfaces, in a fixed order, see `aov::MaterialIds`
+    fn materials<'a>(&'a self, _materials: &mut Vec<&'a Material>) {}
+}
+
+// shared objects, see `instance::Instance`
+impl<T: Reflexible + ?Sized> Reflexible for Arc<T> {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        (**self).hit(r, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        (**self).bounding_box()
+    }
+
+    fn lights<'a>(&'a self, lights: &mut Vec<Box<dyn Light + 'a>>) {
+        (**self).lights(lights)
+    }
+
+    fn materials<'a>(&'a self, materials: &mut Vec<&'a Material>) {
+        (**self).materials(materials)
+    }
 }
 
 pub struct ReflexibleList {
@@ -46,17 +118,43 @@ impl ReflexibleList {
 }
 
 impl Reflexible for ReflexibleList {
-    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
         let mut hit_record = None;
         let mut closest_so_far = t_max;
 
-        for object in &self.list {
+        for (index, object) in self.list.iter().enumerate() {
             if let Some(hit) = object.hit(r, t_min, closest_so_far) {
-                hit_record = Some(hit);
+                hit_record = Some(hit.with_object(index + 1));
                 closest_so_far = hit.t;
             }
         }
 
         hit_record
     }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let mut bbox: Option<Aabb> = None;
+
+        for object in &self.list {
+            let object_box = object.bounding_box()?;
+            bbox = Some(match bbox {
+                Some(bbox) => Aabb::surrounding(&bbox, &object_box),
+                None => object_box,
+            });
+        }
+
+        bbox
+    }
+
+    fn lights<'a>(&'a self, lights: &mut Vec<Box<dyn Light + 'a>>) {
+        for object in &self.list {
+            object.lights(lights);
+        }
+    }
+
+    fn materials<'a>(&'a self, materials: &mut Vec<&'a Material>) {
+        for object in &self.list {
+            object.materials(materials);
+        }
+    }
 }
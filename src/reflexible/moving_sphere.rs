@@ -0,0 +1,105 @@
+use crate::materials::Material;
+use crate::ray::Ray;
+use crate::reflexible::aabb::Aabb;
+use crate::reflexible::sphere::hit_sphere;
+use crate::reflexible::HitRecord;
+use crate::reflexible::Reflexible;
+use crate::vec3::Vec3;
+
+use std::sync::Arc;
+
+// a sphere moving in a straight line from `center0` at `time0` to `center1` at `time1`,
+// standing still before and after
+pub struct MovingSphere {
+    center0: Vec3,
+    center1: Vec3,
+    time0: f64,
+    time1: f64,
+    radius: f64,
+    material: Arc<Material>,
+}
+
+impl MovingSphere {
+    pub fn new<M: Into<Arc<Material>>>(
+        center0: Vec3,
+        center1: Vec3,
+        time0: f64,
+        time1: f64,
+        radius: f64,
+        material: M,
+    ) -> MovingSphere {
+        MovingSphere {
+            center0,
+            center1,
+            time0,
+            time1,
+            radius,
+            material: material.into(),
+        }
+    }
+
+    pub fn center(&self, time: f64) -> Vec3 {
+        if time <= self.time0 {
+            return self.center0;
+        }
+        if time >= self.time1 {
+            return self.center1;
+        }
+        self.center0
+            + ((time - self.time0) / (self.time1 - self.time0)) * (self.center1 - self.center0)
+    }
+}
+
+impl Reflexible for MovingSphere {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        hit_sphere(
+            self.center(r.time),
+            self.radius,
+            &self.material,
+            r,
+            t_min,
+            t_max,
+        )
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let radius = Vec3::new(self.radius.abs(), self.radius.abs(), self.radius.abs());
+        Some(Aabb::surrounding(
+            &Aabb::new(self.center0 - radius, self.center0 + radius),
+            &Aabb::new(self.center1 - radius, self.center1 + radius),
+        ))
+    }
+
+    fn materials<'a>(&'a self, materials: &mut Vec<&'a Material>) {
+        materials.push(&self.material);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::lambertian::Lambertian;
+
+    #[test]
+    fn moves() {
+        let sphere = MovingSphere::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 2.0, 0.0),
+            0.0,
+            1.0,
+            0.5,
+            Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+        );
+        assert_eq!(sphere.center(0.25), Vec3::new(0.0, 0.5, 0.0));
+        assert_eq!(sphere.center(2.0), Vec3::new(0.0, 2.0, 0.0));
+
+        let r = Ray::new(Vec3::new(0.0, 2.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(sphere.hit(&r, 0.001, f64::MAX).is_none());
+        let rec = sphere.hit(&r.with_time(1.0), 0.001, f64::MAX).unwrap();
+        assert_eq!(rec.t, 4.5);
+
+        let bbox = sphere.bounding_box().unwrap();
+        assert_eq!(bbox.min, Vec3::new(-0.5, -0.5, -0.5));
+        assert_eq!(bbox.max, Vec3::new(0.5, 2.5, 0.5));
+    }
+}
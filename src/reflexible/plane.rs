@@ -0,0 +1,106 @@
+use crate::materials::Material;
+use crate::ray::Ray;
+use crate::reflexible::aabb::Aabb;
+use crate::reflexible::HitRecord;
+use crate::reflexible::Reflexible;
+use crate::vec3::Vec3;
+
+use std::sync::Arc;
+
+const EPSILON: f64 = 1e-9;
+
+// an infinite plane through `point` facing `normal`. u and v are the coordinates along two
+// directions in the plane, repeating every unit so that image textures tile.
+pub struct Plane {
+    point: Vec3,
+    normal: Vec3,
+    u_axis: Vec3,
+    v_axis: Vec3,
+    material: Arc<Material>,
+}
+
+impl Plane {
+    pub fn new<M: Into<Arc<Material>>>(point: Vec3, normal: Vec3, material: M) -> Plane {
+        // v turned around, so that a floor's u goes along x and v along z
+        let (u_axis, v, normal) = Vec3::basis(normal);
+        let v_axis = -v;
+
+        Plane {
+            point,
+            normal,
+            u_axis,
+            v_axis,
+            material: material.into(),
+        }
+    }
+}
+
+impl Reflexible for Plane {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let denom = self.normal.dot(&r.direction);
+        // ray parallel to the plane
+        if denom.abs() < EPSILON {
+            return None;
+        }
+
+        let t = (self.point - r.origin).dot(&self.normal) / denom;
+        if t < t_max && t > t_min {
+            let p = r.point_at_parameter(t);
+            let d = p - self.point;
+            let u = d.dot(&self.u_axis).rem_euclid(1.0);
+            let v = d.dot(&self.v_axis).rem_euclid(1.0);
+            Some(
+                HitRecord::new(t, p, self.normal, &self.material)
+                    .with_uv(u, v)
+                    .facing(r),
+            )
+        } else {
+            None
+        }
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        None
+    }
+
+    fn materials<'a>(&'a self, materials: &mut Vec<&'a Material>) {
+        materials.push(&self.material);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::lambertian::Lambertian;
+
+    #[test]
+    fn hit() {
+        let floor = Plane::new(
+            Vec3::new(0.0, -1.0, 0.0),
+            Vec3::new(0.0, 2.0, 0.0),
+            Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+        );
+
+        let r = Ray::new(Vec3::new(10.25, 1.0, -3.5), Vec3::new(0.0, -1.0, 0.0));
+        let rec = floor.hit(&r, 0.001, f64::MAX).unwrap();
+        assert_eq!(rec.t, 2.0);
+        assert_eq!(rec.normal, Vec3::new(0.0, 1.0, 0.0));
+        // a floor's u goes along x and v along z
+        assert_eq!((rec.u, rec.v), (0.25, 0.5));
+
+        assert!(rec.front_face);
+
+        // from below the normal faces down
+        let r = Ray::new(Vec3::new(0.0, -3.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let rec = floor.hit(&r, 0.001, f64::MAX).unwrap();
+        assert_eq!(rec.t, 2.0);
+        assert_eq!(rec.normal, Vec3::new(0.0, -1.0, 0.0));
+        assert!(!rec.front_face);
+
+        let up = Ray::new(Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let parallel = Ray::new(Vec3::new(0.0, 1.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!(floor.hit(&up, 0.001, f64::MAX).is_none());
+        assert!(floor.hit(&parallel, 0.001, f64::MAX).is_none());
+        assert!(floor.bounding_box().is_none());
+    }
+}
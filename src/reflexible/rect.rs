@@ -0,0 +1,341 @@
+use crate::lights::Light;
+use crate::materials::Material;
+use crate::ray::Ray;
+use crate::reflexible::aabb::{axis_of, Aabb, BOX_PADDING};
+use crate::reflexible::HitRecord;
+use crate::reflexible::Reflexible;
+use crate::sampler::Sampler;
+use crate::vec3::Vec3;
+
+use std::sync::Arc;
+
+// the rectangle [a0, a1] x [b0, b1] on axes `a` and `b` at `k` on axis `c` (0 = x, 1 = y,
+// 2 = z). the normal points along `c`, or against it when flipped. u goes along `a` and v
+// along `b`, both from 0 to 1.
+struct Rect {
+    axes: (usize, usize, usize),
+    a0: f64,
+    a1: f64,
+    b0: f64,
+    b1: f64,
+    k: f64,
+    flipped: bool,
+    material: Arc<Material>,
+}
+
+impl Rect {
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        axes: (usize, usize, usize),
+        a0: f64,
+        a1: f64,
+        b0: f64,
+        b1: f64,
+        k: f64,
+        material: Arc<Material>,
+    ) -> Rect {
+        Rect {
+            axes,
+            a0,
+            a1,
+            b0,
+            b1,
+            k,
+            flipped: false,
+            material,
+        }
+    }
+
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let (a, b, c) = self.axes;
+        // parallel rays give an infinite or nan t and miss
+        let t = (self.k - axis_of(&r.origin, c)) / axis_of(&r.direction, c);
+        if !(t > t_min && t < t_max) {
+            return None;
+        }
+
+        let p = r.point_at_parameter(t);
+        let (pa, pb) = (axis_of(&p, a), axis_of(&p, b));
+        if pa < self.a0 || pa > self.a1 || pb < self.b0 || pb > self.b1 {
+            return None;
+        }
+
+        let normal = self.point(0.0, 0.0, if self.flipped { -1.0 } else { 1.0 });
+        let u = (pa - self.a0) / (self.a1 - self.a0);
+        let v = (pb - self.b0) / (self.b1 - self.b0);
+        Some(
+            HitRecord::new(t, p, normal, &self.material)
+                .with_uv(u, v)
+                .facing(r),
+        )
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::new(
+            self.point(self.a0, self.b0, self.k - BOX_PADDING),
+            self.point(self.a1, self.b1, self.k + BOX_PADDING),
+        )
+    }
+
+    // the point with coordinates a, b and c on the rectangle's axes
+    fn point(&self, a: f64, b: f64, c: f64) -> Vec3 {
+        let mut p = [0.0; 3];
+        p[self.axes.0] = a;
+        p[self.axes.1] = b;
+        p[self.axes.2] = c;
+        Vec3::new(p[0], p[1], p[2])
+    }
+
+    // the solid angle density of the point `to_point` away, for points picked uniformly
+    // over the area
+    fn pdf_to(&self, to_point: Vec3) -> f64 {
+        let area = (self.a1 - self.a0) * (self.b1 - self.b0);
+        let distance = to_point.length();
+        let cosine = axis_of(&to_point, self.axes.2).abs() / distance;
+        if cosine > 0.0 {
+            distance * distance / (cosine * area)
+        } else {
+            0.0
+        }
+    }
+
+    fn lights<'a>(&'a self, lights: &mut Vec<Box<dyn Light + 'a>>) {
+        if self.material.is_emissive() {
+            lights.push(Box::new(self));
+        }
+    }
+}
+
+// picks points uniformly over the area, lit from both sides
+impl Light for Rect {
+    fn sample(&self, origin: Vec3, sampler: &mut Sampler) -> Option<(Vec3, f64)> {
+        let a = self.a0 + sampler.next_f64() * (self.a1 - self.a0);
+        let b = self.b0 + sampler.next_f64() * (self.b1 - self.b0);
+        let direction = self.point(a, b, self.k) - origin;
+
+        match self.pdf_to(direction) {
+            pdf if pdf > 0.0 => Some((direction, pdf)),
+            // seen edge on
+            _ => None,
+        }
+    }
+
+    fn pdf(&self, origin: Vec3, direction: Vec3) -> f64 {
+        match self.hit(&Ray::new(origin, direction), 0.0, f64::MAX) {
+            Some(rec) => self.pdf_to(rec.p - origin),
+            None => 0.0,
+        }
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+}
+
+// [x0, x1] x [y0, y1] at z = k, facing +z
+pub struct XyRect(Rect);
+
+impl XyRect {
+    pub fn new<M: Into<Arc<Material>>>(
+        x0: f64,
+        x1: f64,
+        y0: f64,
+        y1: f64,
+        k: f64,
+        material: M,
+    ) -> XyRect {
+        XyRect(Rect::new((0, 1, 2), x0, x1, y0, y1, k, material.into()))
+    }
+
+    // facing -z instead
+    pub fn flipped(mut self) -> XyRect {
+        self.0.flipped = !self.0.flipped;
+        self
+    }
+}
+
+impl Reflexible for XyRect {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        self.0.hit(r, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.0.bounding_box())
+    }
+
+    fn lights<'a>(&'a self, lights: &mut Vec<Box<dyn Light + 'a>>) {
+        self.0.lights(lights)
+    }
+
+    fn materials<'a>(&'a self, materials: &mut Vec<&'a Material>) {
+        materials.push(&self.0.material);
+    }
+}
+
+// [x0, x1] x [z0, z1] at y = k, facing +y
+pub struct XzRect(Rect);
+
+impl XzRect {
+    pub fn new<M: Into<Arc<Material>>>(
+        x0: f64,
+        x1: f64,
+        z0: f64,
+        z1: f64,
+        k: f64,
+        material: M,
+    ) -> XzRect {
+        XzRect(Rect::new((0, 2, 1), x0, x1, z0, z1, k, material.into()))
+    }
+
+    // facing -y instead
+    pub fn flipped(mut self) -> XzRect {
+        self.0.flipped = !self.0.flipped;
+        self
+    }
+}
+
+impl Reflexible for XzRect {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        self.0.hit(r, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.0.bounding_box())
+    }
+
+    fn lights<'a>(&'a self, lights: &mut Vec<Box<dyn Light + 'a>>) {
+        self.0.lights(lights)
+    }
+
+    fn materials<'a>(&'a self, materials: &mut Vec<&'a Material>) {
+        materials.push(&self.0.material);
+    }
+}
+
+// [y0, y1] x [z0, z1] at x = k, facing +x
+pub struct YzRect(Rect);
+
+impl YzRect {
+    pub fn new<M: Into<Arc<Material>>>(
+        y0: f64,
+        y1: f64,
+        z0: f64,
+        z1: f64,
+        k: f64,
+        material: M,
+    ) -> YzRect {
+        YzRect(Rect::new((1, 2, 0), y0, y1, z0, z1, k, material.into()))
+    }
+
+    // facing -x instead
+    pub fn flipped(mut self) -> YzRect {
+        self.0.flipped = !self.0.flipped;
+        self
+    }
+}
+
+impl Reflexible for YzRect {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        self.0.hit(r, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.0.bounding_box())
+    }
+
+    fn lights<'a>(&'a self, lights: &mut Vec<Box<dyn Light + 'a>>) {
+        self.0.lights(lights)
+    }
+
+    fn materials<'a>(&'a self, materials: &mut Vec<&'a Material>) {
+        materials.push(&self.0.material);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::diffuse_light::DiffuseLight;
+    use crate::materials::lambertian::Lambertian;
+
+    fn material() -> Material {
+        Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)))
+    }
+
+    #[test]
+    fn hit() {
+        let rect = XzRect::new(-1.0, 3.0, 0.0, 2.0, 1.0, material());
+        let r = Ray::new(Vec3::new(0.0, 3.0, 0.5), Vec3::new(0.0, -1.0, 0.0));
+        let rec = rect.hit(&r, 0.001, f64::MAX).unwrap();
+
+        assert_eq!(rec.t, 2.0);
+        assert_eq!(rec.p, Vec3::new(0.0, 1.0, 0.5));
+        assert_eq!(rec.normal, Vec3::new(0.0, 1.0, 0.0));
+        assert!(rec.front_face);
+        assert_eq!((rec.u, rec.v), (0.25, 0.25));
+
+        // a flipped rectangle faces down, so this ray hits its back
+        let flipped = XzRect::new(-1.0, 3.0, 0.0, 2.0, 1.0, material()).flipped();
+        let rec = flipped.hit(&r, 0.001, f64::MAX).unwrap();
+        assert_eq!(rec.normal, Vec3::new(0.0, 1.0, 0.0));
+        assert!(!rec.front_face);
+    }
+
+    #[test]
+    fn miss() {
+        let rect = YzRect::new(0.0, 1.0, 0.0, 1.0, 0.0, material());
+        let outside = Ray::new(Vec3::new(1.0, 1.5, 0.5), Vec3::new(-1.0, 0.0, 0.0));
+        let parallel = Ray::new(Vec3::new(1.0, 0.5, 0.5), Vec3::new(0.0, 1.0, 0.0));
+        let behind = Ray::new(Vec3::new(1.0, 0.5, 0.5), Vec3::new(1.0, 0.0, 0.0));
+
+        assert!(rect.hit(&outside, 0.001, f64::MAX).is_none());
+        assert!(rect.hit(&parallel, 0.001, f64::MAX).is_none());
+        assert!(rect.hit(&behind, 0.001, f64::MAX).is_none());
+
+        let bbox = XyRect::new(0.0, 1.0, 2.0, 3.0, 4.0, material())
+            .bounding_box()
+            .unwrap();
+        assert_eq!(bbox.min, Vec3::new(0.0, 2.0, 4.0 - BOX_PADDING));
+        assert_eq!(bbox.max, Vec3::new(1.0, 3.0, 4.0 + BOX_PADDING));
+    }
+
+    #[test]
+    fn light() {
+        let rect = XzRect::new(
+            -1.0,
+            1.0,
+            -2.0,
+            2.0,
+            3.0,
+            Material::DiffuseLight(DiffuseLight::new(Vec3::new(1.0, 1.0, 1.0))),
+        )
+        .flipped();
+        let mut lights = Vec::new();
+        rect.lights(&mut lights);
+        let light = &lights[0];
+
+        // straight below the center the density is distance squared over area
+        let origin = Vec3::new(0.0, 1.0, 0.0);
+        assert_eq!(light.pdf(origin, Vec3::new(0.0, 1.0, 0.0)), 0.5);
+        assert_eq!(light.pdf(origin, Vec3::new(0.0, -1.0, 0.0)), 0.0);
+        assert_eq!(light.pdf(origin, Vec3::new(5.0, 1.0, 0.0)), 0.0);
+
+        let mut sampler = Sampler::new(5);
+        for _ in 0..100 {
+            let (direction, pdf) = light.sample(origin, &mut sampler).unwrap();
+            assert!((light.pdf(origin, direction) - pdf).abs() < 1e-9 * pdf);
+            assert!(rect
+                .hit(&Ray::new(origin, direction), 0.001, 1.001)
+                .is_some());
+        }
+
+        // edge on
+        assert!(light
+            .sample(Vec3::new(3.0, 3.0, 0.0), &mut sampler)
+            .is_none());
+        let dark = YzRect::new(0.0, 1.0, 0.0, 1.0, 0.0, material());
+        let mut none = Vec::new();
+        dark.lights(&mut none);
+        assert!(none.is_empty());
+    }
+}
@@ -1,50 +1,210 @@
+use crate::lights::Light;
 use crate::materials::Material;
 use crate::ray::Ray;
+use crate::reflexible::aabb::Aabb;
 use crate::reflexible::HitRecord;
 use crate::reflexible::Reflexible;
+use crate::sampler::Sampler;
 use crate::vec3::Vec3;
 
+use std::f64::consts::PI;
+use std::sync::Arc;
+
 pub struct Sphere {
     center: Vec3,
     radius: f64,
-    material: Material,
+    material: Arc<Material>,
 }
 
 impl Sphere {
-    pub fn new(center: Vec3, radius: f64, material: Material) -> Sphere {
+    pub fn new<M: Into<Arc<Material>>>(center: Vec3, radius: f64, material: M) -> Sphere {
         Sphere {
             center,
             radius,
-            material,
+            material: material.into(),
+        }
+    }
+
+    // one minus the cosine of the angle between the center and the outline, as seen from
+    // `origin`, worked out so that it stays precise for far away spheres. None from inside.
+    fn cone(&self, origin: Vec3) -> Option<f64> {
+        let ratio = self.radius * self.radius / (self.center - origin).squared_length();
+        if ratio >= 1.0 {
+            return None;
         }
+        Some(ratio / (1.0 + f64::sqrt(1.0 - ratio)))
     }
 }
 
 impl Reflexible for Sphere {
-    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        let oc = r.origin - self.center;
-        let a = r.direction.dot(&r.direction);
-        let b = oc.dot(&r.direction) as f64;
-        let c = oc.dot(&oc) - self.radius * self.radius;
-        let discriminant = b * b - a * c;
-
-        if discriminant > 0.0 {
-            let temp = (-b - f64::sqrt(discriminant)) / a;
-            if temp < t_max && temp > t_min {
-                let t = temp;
-                let p = r.point_at_parameter(t);
-                let normal = (p - self.center) / self.radius;
-                return Some(HitRecord::new(t, p, normal, self.material));
-            }
-            let temp = (-b + f64::sqrt(discriminant)) / a;
-            if temp < t_max && temp > t_min {
-                let t = temp;
-                let p = r.point_at_parameter(t);
-                let normal = (p - self.center) / self.radius;
-                return Some(HitRecord::new(t, p, normal, self.material));
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        hit_sphere(self.center, self.radius, &self.material, r, t_min, t_max)
+    }
+
+    // negative radii make hollow spheres, with normals pointing in
+    fn bounding_box(&self) -> Option<Aabb> {
+        let radius = Vec3::new(self.radius.abs(), self.radius.abs(), self.radius.abs());
+        Some(Aabb::new(self.center - radius, self.center + radius))
+    }
+
+    fn lights<'a>(&'a self, lights: &mut Vec<Box<dyn Light + 'a>>) {
+        if self.material.is_emissive() {
+            lights.push(Box::new(self));
+        }
+    }
+
+    fn materials<'a>(&'a self, materials: &mut Vec<&'a Material>) {
+        materials.push(&self.material);
+    }
+}
+
+// picks directions uniformly in the cone the sphere covers
+impl Light for Sphere {
+    fn sample(&self, origin: Vec3, sampler: &mut Sampler) -> Option<(Vec3, f64)> {
+        let cone = self.cone(origin)?;
+        let (u, v, w) = Vec3::basis(self.center - origin);
+
+        let cosine = 1.0 - sampler.next_f64() * cone;
+        let sine = f64::sqrt((1.0 - cosine * cosine).max(0.0));
+        let phi = 2.0 * PI * sampler.next_f64();
+        let direction = f64::cos(phi) * sine * u + f64::sin(phi) * sine * v + cosine * w;
+
+        Some((direction, 1.0 / (2.0 * PI * cone)))
+    }
+
+    fn pdf(&self, origin: Vec3, direction: Vec3) -> f64 {
+        match self.cone(origin) {
+            Some(cone)
+                if Vec3::unit_vector(direction).dot(&Vec3::unit_vector(self.center - origin))
+                    >= 1.0 - cone =>
+            {
+                1.0 / (2.0 * PI * cone)
             }
+            _ => 0.0,
+        }
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+}
+
+pub fn hit_sphere<'a>(
+    center: Vec3,
+    radius: f64,
+    material: &'a Material,
+    r: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<HitRecord<'a>> {
+    let oc = r.origin - center;
+    let a = r.direction.dot(&r.direction);
+    let b = oc.dot(&r.direction);
+    let c = oc.dot(&oc) - radius * radius;
+    let discriminant = b * b - a * c;
+
+    if discriminant > 0.0 {
+        let temp = (-b - f64::sqrt(discriminant)) / a;
+        if temp < t_max && temp > t_min {
+            let t = temp;
+            let p = r.point_at_parameter(t);
+            let normal = (p - center) / radius;
+            let (u, v) = sphere_uv(&normal);
+            return Some(
+                HitRecord::new(t, p, normal, material)
+                    .with_uv(u, v)
+                    .facing(r),
+            );
+        }
+        let temp = (-b + f64::sqrt(discriminant)) / a;
+        if temp < t_max && temp > t_min {
+            let t = temp;
+            let p = r.point_at_parameter(t);
+            let normal = (p - center) / radius;
+            let (u, v) = sphere_uv(&normal);
+            return Some(
+                HitRecord::new(t, p, normal, material)
+                    .with_uv(u, v)
+                    .facing(r),
+            );
+        }
+    }
+
+    None
+}
+
+// u is the angle around the y axis starting from -x, v goes from the bottom (-y) to the top
+pub fn sphere_uv(p: &Vec3) -> (f64, f64) {
+    let theta = f64::acos(-p.y);
+    let phi = f64::atan2(-p.z, p.x) + std::f64::consts::PI;
+
+    (
+        phi / (2.0 * std::f64::consts::PI),
+        theta / std::f64::consts::PI,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::dielectric::Dielectric;
+    use crate::materials::diffuse_light::DiffuseLight;
+    use crate::reflexible::bvh::Bvh;
+
+    #[test]
+    fn uv() {
+        let close =
+            |a: (f64, f64), b: (f64, f64)| (a.0 - b.0).abs() < 1e-12 && (a.1 - b.1).abs() < 1e-12;
+
+        assert!(close(sphere_uv(&Vec3::new(1.0, 0.0, 0.0)), (0.5, 0.5)));
+        assert!(close(sphere_uv(&Vec3::new(0.0, 1.0, 0.0)), (0.5, 1.0)));
+        assert!(close(sphere_uv(&Vec3::new(0.0, -1.0, 0.0)), (0.5, 0.0)));
+        assert!(close(sphere_uv(&Vec3::new(0.0, 0.0, 1.0)), (0.25, 0.5)));
+    }
+
+    #[test]
+    fn negative_radius() {
+        let hollow = Sphere::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            -1.0,
+            Material::Dielectric(Dielectric::new(1.5)),
+        );
+        let bbox = hollow.bounding_box().unwrap();
+        assert_eq!(bbox.min, Vec3::new(-1.0, -1.0, -1.0));
+        assert_eq!(bbox.max, Vec3::new(1.0, 1.0, 1.0));
+
+        let world = Bvh::new(vec![Box::new(hollow)]);
+        let r = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        let rec = world.hit(&r, 0.001, f64::MAX).unwrap();
+        assert!((rec.t - 4.0).abs() < 1e-9);
+        // the normal points in, so glass refracts as if leaving
+        assert!(!rec.front_face);
+    }
+
+    #[test]
+    fn light() {
+        let sphere = Sphere::new(
+            Vec3::new(0.0, 0.0, -10.0),
+            1.0,
+            Material::DiffuseLight(DiffuseLight::new(Vec3::new(1.0, 1.0, 1.0))),
+        );
+        let origin = Vec3::new(0.0, 0.0, 0.0);
+        // one over the solid angle of the cone
+        let expected = 1.0 / (2.0 * PI * (1.0 - f64::sqrt(0.99)));
+
+        let mut sampler = Sampler::new(3);
+        for _ in 0..100 {
+            let (direction, pdf) = sphere.sample(origin, &mut sampler).unwrap();
+            assert!((pdf - expected).abs() < 1e-9 * expected);
+            assert_eq!(sphere.pdf(origin, direction), pdf);
+            assert!(sphere
+                .hit(&Ray::new(origin, direction), 0.001, f64::MAX)
+                .is_some());
         }
 
-        None
+        assert_eq!(sphere.pdf(origin, Vec3::new(0.0, 1.0, -1.0)), 0.0);
+        assert!(sphere
+            .sample(Vec3::new(0.0, 0.5, -10.0), &mut sampler)
+            .is_none());
     }
 }
@@ -0,0 +1,220 @@
+use crate::materials::Material;
+use crate::ray::Ray;
+use crate::reflexible::aabb::{Aabb, BOX_PADDING};
+use crate::reflexible::HitRecord;
+use crate::reflexible::Reflexible;
+use crate::vec3::Vec3;
+
+use std::sync::Arc;
+
+const EPSILON: f64 = 1e-9;
+
+pub struct Triangle {
+    vertices: [Vec3; 3],
+    normals: Option<[Vec3; 3]>,
+    uvs: Option<[(f64, f64); 3]>,
+    material: Arc<Material>,
+}
+
+impl Triangle {
+    pub fn new<M: Into<Arc<Material>>>(v0: Vec3, v1: Vec3, v2: Vec3, material: M) -> Triangle {
+        Triangle {
+            vertices: [v0, v1, v2],
+            normals: None,
+            uvs: None,
+            material: material.into(),
+        }
+    }
+
+    pub fn with_normals(mut self, n0: Vec3, n1: Vec3, n2: Vec3) -> Triangle {
+        self.normals = Some([n0, n1, n2]);
+        self
+    }
+
+    pub fn with_uvs(mut self, uv0: (f64, f64), uv1: (f64, f64), uv2: (f64, f64)) -> Triangle {
+        self.uvs = Some([uv0, uv1, uv2]);
+        self
+    }
+}
+
+impl Reflexible for Triangle {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let [v0, v1, v2] = self.vertices;
+        let (t, b1, b2) = intersect(v0, v1, v2, r, t_min, t_max)?;
+
+        Some(shade(
+            r,
+            t,
+            b1,
+            b2,
+            &self.vertices,
+            self.normals.as_ref(),
+            self.uvs.as_ref(),
+            &self.material,
+        ))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let [v0, v1, v2] = self.vertices;
+        Some(bounds(v0, v1, v2))
+    }
+
+    fn materials<'a>(&'a self, materials: &mut Vec<&'a Material>) {
+        materials.push(&self.material);
+    }
+}
+
+// möller-trumbore, returns t and the barycentric coordinates of v1 and v2
+pub fn intersect(
+    v0: Vec3,
+    v1: Vec3,
+    v2: Vec3,
+    r: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<(f64, f64, f64)> {
+    let edge1 = v1 - v0;
+    let edge2 = v2 - v0;
+    let pvec = Vec3::cross(r.direction, edge2);
+    let det = edge1.dot(&pvec);
+
+    // ray parallel to the triangle plane
+    if det.abs() < EPSILON {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+
+    let tvec = r.origin - v0;
+    let b1 = tvec.dot(&pvec) * inv_det;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+
+    let qvec = Vec3::cross(tvec, edge1);
+    let b2 = r.direction.dot(&qvec) * inv_det;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+
+    let t = edge2.dot(&qvec) * inv_det;
+    if t < t_max && t > t_min {
+        Some((t, b1, b2))
+    } else {
+        None
+    }
+}
+
+// builds the hit record, interpolating vertex normals and uvs when present.
+// without uvs the barycentric coordinates are used instead. the normal is turned against
+// the ray and the winding tells the front face, so meshes with mixed up windings or
+// vertex normals still get lit on the side they are seen from.
+#[allow(clippy::too_many_arguments)]
+pub fn shade<'a>(
+    r: &Ray,
+    t: f64,
+    b1: f64,
+    b2: f64,
+    vertices: &[Vec3; 3],
+    normals: Option<&[Vec3; 3]>,
+    uvs: Option<&[(f64, f64); 3]>,
+    material: &'a Material,
+) -> HitRecord<'a> {
+    let b0 = 1.0 - b1 - b2;
+
+    let geometric = Vec3::cross(vertices[1] - vertices[0], vertices[2] - vertices[0]);
+    let normal = match normals {
+        Some(n) => Vec3::unit_vector(b0 * n[0] + b1 * n[1] + b2 * n[2]),
+        None => Vec3::unit_vector(geometric),
+    };
+    let (u, v) = match uvs {
+        Some(uv) => (
+            b0 * uv[0].0 + b1 * uv[1].0 + b2 * uv[2].0,
+            b0 * uv[0].1 + b1 * uv[1].1 + b2 * uv[2].1,
+        ),
+        None => (b1, b2),
+    };
+
+    let rec = HitRecord::new(t, r.point_at_parameter(t), normal, material)
+        .with_uv(u, v)
+        .facing(r);
+    HitRecord {
+        front_face: geometric.dot(&r.direction) < 0.0,
+        ..rec
+    }
+}
+
+pub fn bounds(v0: Vec3, v1: Vec3, v2: Vec3) -> Aabb {
+    let padding = Vec3::new(BOX_PADDING, BOX_PADDING, BOX_PADDING);
+    let min = Vec3::new(
+        v0.x.min(v1.x).min(v2.x),
+        v0.y.min(v1.y).min(v2.y),
+        v0.z.min(v1.z).min(v2.z),
+    );
+    let max = Vec3::new(
+        v0.x.max(v1.x).max(v2.x),
+        v0.y.max(v1.y).max(v2.y),
+        v0.z.max(v1.z).max(v2.z),
+    );
+
+    Aabb::new(min - padding, max + padding)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::lambertian::Lambertian;
+
+    fn triangle() -> Triangle {
+        Triangle::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+        )
+    }
+
+    #[test]
+    fn hit() {
+        let r = Ray::new(Vec3::new(0.25, 0.25, 2.0), Vec3::new(0.0, 0.0, -1.0));
+        let tri = triangle();
+        let rec = tri.hit(&r, 0.001, f64::MAX).unwrap();
+
+        assert!((rec.t - 2.0).abs() < 1e-12);
+        assert_eq!(rec.normal, Vec3::new(0.0, 0.0, 1.0));
+        assert!(rec.front_face);
+        assert!((rec.u - 0.25).abs() < 1e-12 && (rec.v - 0.25).abs() < 1e-12);
+
+        let behind = Ray::new(Vec3::new(0.25, 0.25, -2.0), Vec3::new(0.0, 0.0, 1.0));
+        let rec = tri.hit(&behind, 0.001, f64::MAX).unwrap();
+        assert_eq!(rec.normal, Vec3::new(0.0, 0.0, -1.0));
+        assert!(!rec.front_face);
+    }
+
+    #[test]
+    fn miss() {
+        let outside = Ray::new(Vec3::new(0.75, 0.75, 2.0), Vec3::new(0.0, 0.0, -1.0));
+        let parallel = Ray::new(Vec3::new(0.25, 0.25, 2.0), Vec3::new(1.0, 0.0, 0.0));
+
+        assert!(triangle().hit(&outside, 0.001, f64::MAX).is_none());
+        assert!(triangle().hit(&parallel, 0.001, f64::MAX).is_none());
+    }
+
+    #[test]
+    fn smooth_normals() {
+        let n = Vec3::unit_vector(Vec3::new(1.0, 0.0, 1.0));
+        let tri = triangle()
+            .with_normals(n, n, n)
+            .with_uvs((0.0, 0.0), (2.0, 0.0), (0.0, 2.0));
+        let r = Ray::new(Vec3::new(0.25, 0.5, 2.0), Vec3::new(0.0, 0.0, -1.0));
+        let rec = tri.hit(&r, 0.001, f64::MAX).unwrap();
+
+        assert!((rec.normal - n).length() < 1e-12);
+        assert!((rec.u - 0.5).abs() < 1e-12 && (rec.v - 1.0).abs() < 1e-12);
+
+        // vertex normals pointing away from the winding's front still face the ray
+        let tri = triangle().with_normals(-n, -n, -n);
+        let rec = tri.hit(&r, 0.001, f64::MAX).unwrap();
+        assert!((rec.normal - n).length() < 1e-12);
+        assert!(rec.front_face);
+    }
+}
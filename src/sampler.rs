@@ -0,0 +1,115 @@
+use rand::{Error, RngCore};
+
+const PCG_MULTIPLIER: u64 = 6364136223846793005;
+
+// pcg32 random number generator. every camera sample gets its own sampler derived from the
+// render seed, the pixel and the sample index, so results don't depend on which thread or
+// in which order the samples are taken.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sampler {
+    state: u64,
+    inc: u64,
+}
+
+impl Sampler {
+    pub fn new(seed: u64) -> Sampler {
+        Sampler::with_stream(seed, 0)
+    }
+
+    pub fn with_stream(seed: u64, stream: u64) -> Sampler {
+        let mut sampler = Sampler {
+            state: 0,
+            inc: (stream << 1) | 1,
+        };
+        sampler.step();
+        sampler.state = sampler.state.wrapping_add(seed);
+        sampler.step();
+        sampler
+    }
+
+    pub fn for_sample(seed: u64, x: u32, y: u32, sample: u32) -> Sampler {
+        let pixel = (u64::from(y) << 32) | u64::from(x);
+        let stream = mix(seed ^ mix(pixel));
+        Sampler::with_stream(mix(stream ^ u64::from(sample)), stream)
+    }
+
+    // uniform in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    fn step(&mut self) {
+        self.state = self
+            .state
+            .wrapping_mul(PCG_MULTIPLIER)
+            .wrapping_add(self.inc);
+    }
+}
+
+impl RngCore for Sampler {
+    fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.step();
+
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        let rot = (old >> 59) as u32;
+        xorshifted.rotate_right(rot)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        (u64::from(self.next_u32()) << 32) | u64::from(self.next_u32())
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(4) {
+            let bytes = self.next_u32().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+// splitmix64 finalizer
+fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn deterministic() {
+        let mut a = Sampler::for_sample(42, 3, 7, 0);
+        let mut b = Sampler::for_sample(42, 3, 7, 0);
+        let mut c = Sampler::for_sample(42, 3, 7, 1);
+
+        let a: Vec<u32> = (0..8).map(|_| a.next_u32()).collect();
+        let b: Vec<u32> = (0..8).map(|_| b.next_u32()).collect();
+        let c: Vec<u32> = (0..8).map(|_| c.next_u32()).collect();
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn unit_interval() {
+        let mut sampler = Sampler::new(1);
+        let mut sum = 0.0;
+
+        for _ in 0..10000 {
+            let x = sampler.next_f64();
+            assert!((0.0..1.0).contains(&x));
+            sum += x;
+        }
+
+        assert!((sum / 10000.0 - 0.5).abs() < 0.01);
+    }
+}
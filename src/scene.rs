@@ -0,0 +1,1451 @@
+// declarative toml scenes. a scene file has a [camera] table, optional [image] and [render]
+// tables, named [textures.*] and [materials.*] and an [[objects]] array referring to the
+// materials by name. relative file paths are resolved against the scene file.
+
+use crate::adaptive::AdaptiveSampling;
+use crate::aov::Aov;
+use crate::camera::Camera;
+use crate::error::Error;
+use crate::integrators::ambient_occlusion::AmbientOcclusion;
+use crate::integrators::debug::DebugView;
+use crate::integrators::path::PathTracer;
+use crate::integrators::BuiltinIntegrator;
+use crate::materials::{
+    dielectric::Dielectric, diffuse_light::DiffuseLight, lambertian::Lambertian, metal::Metal,
+    Material,
+};
+use crate::obj;
+use crate::reflexible::box_shape::BoxShape;
+use crate::reflexible::instance::Instance;
+use crate::reflexible::mesh::TriangleMesh;
+use crate::reflexible::moving_sphere::MovingSphere;
+use crate::reflexible::plane::Plane;
+use crate::reflexible::rect::{XyRect, XzRect, YzRect};
+use crate::reflexible::sphere::Sphere;
+use crate::reflexible::triangle::Triangle;
+use crate::reflexible::{Reflexible, ReflexibleList};
+use crate::settings::{Background, Progressive, RenderSettings};
+use crate::textures::{
+    checker::Checker,
+    image_texture::ImageTexture,
+    noise::{NoiseStyle, NoiseTexture},
+    Texture,
+};
+use crate::tonemap::{self, ToneMap, ToneMapping, Transfer};
+use crate::transform::{AnimatedTransform, Matrix4, Transform};
+use crate::vec3::Vec3;
+
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub struct Scene {
+    pub world: ReflexibleList,
+    pub camera: Camera,
+    pub settings: RenderSettings,
+    pub width: u32,
+    pub height: u32,
+    camera_params: CameraParams,
+}
+
+impl Scene {
+    pub fn new(
+        world: ReflexibleList,
+        camera_params: CameraParams,
+        settings: RenderSettings,
+        width: u32,
+        height: u32,
+    ) -> Scene {
+        Scene {
+            world,
+            camera: camera_params.camera(f64::from(width) / f64::from(height)),
+            settings,
+            width,
+            height,
+            camera_params,
+        }
+    }
+
+    pub fn camera_params(&self) -> &CameraParams {
+        &self.camera_params
+    }
+
+    // changes the image size, rebuilding the camera for the new aspect ratio
+    pub fn set_size(&mut self, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+        self.camera = self
+            .camera_params
+            .camera(f64::from(width) / f64::from(height));
+    }
+}
+
+// everything `Camera::new` needs except the aspect ratio, which follows the image size
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraParams {
+    pub look_from: Vec3,
+    pub look_at: Vec3,
+    pub vup: Vec3,
+    pub vfov: f64,
+    pub aperture: f64,
+    pub focus_dist: f64,
+    pub shutter_open: f64,
+    pub shutter_close: f64,
+}
+
+impl CameraParams {
+    pub fn camera(&self, aspect: f64) -> Camera {
+        Camera::new(
+            &self.look_from,
+            &self.look_at,
+            &self.vup,
+            self.vfov,
+            aspect,
+            self.aperture,
+            self.focus_dist,
+        )
+        .with_shutter(self.shutter_open, self.shutter_close)
+    }
+}
+
+pub fn load_scene<P: AsRef<Path>>(path: P) -> Result<Scene, Error> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path)?;
+    let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+    parse_scene(&source, &path.display().to_string(), base_dir)
+}
+
+pub fn parse_scene(source: &str, file: &str, base_dir: &Path) -> Result<Scene, Error> {
+    let desc: SceneDesc = toml::from_str(source).map_err(|e| toml_error(file, source, e))?;
+
+    let mut builder = Builder {
+        file,
+        base_dir,
+        textures: &desc.textures,
+        resolved_textures: HashMap::new(),
+        resolving: Vec::new(),
+    };
+
+    let mut materials = HashMap::new();
+    for (name, material) in &desc.materials {
+        let entry = format!("materials.{}", name);
+        materials.insert(name.as_str(), Arc::new(builder.material(&entry, material)?));
+    }
+
+    let mut list: Vec<Box<dyn Reflexible>> = Vec::new();
+    let mut loaded_meshes: HashMap<(String, Option<String>), Vec<Arc<TriangleMesh>>> =
+        HashMap::new();
+    for (i, object) in desc.objects.iter().enumerate() {
+        let entry = format!("objects[{}] ({})", i, object.kind());
+        let mut shapes: Vec<Box<dyn Reflexible>> = Vec::new();
+        // objects share their material, except lights, which are told apart by it
+        let material = |name: &str| match materials.get(name) {
+            Some(material) if material.is_emissive() => Ok(Arc::new(Material::clone(material))),
+            Some(material) => Ok(material.clone()),
+            None => Err(builder.error(&entry, format!("unknown material '{}'", name))),
+        };
+        let range = |axis: &str, range: [f64; 2]| {
+            if range[0] < range[1] {
+                Ok((range[0], range[1]))
+            } else {
+                Err(builder.error(
+                    &entry,
+                    format!("{} range [{}, {}] is empty", axis, range[0], range[1]),
+                ))
+            }
+        };
+
+        match *object {
+            ObjectDesc::Sphere {
+                center,
+                radius,
+                ref material_name,
+                ..
+            } => {
+                if radius.is_nan() || radius <= 0.0 {
+                    return Err(
+                        builder.error(&entry, format!("radius must be positive, got {}", radius))
+                    );
+                }
+                shapes.push(Box::new(Sphere::new(
+                    vec3(center),
+                    radius,
+                    material(material_name)?,
+                )));
+            }
+            ObjectDesc::MovingSphere {
+                center0,
+                center1,
+                time0,
+                time1,
+                radius,
+                ref material_name,
+                ..
+            } => {
+                if radius.is_nan() || radius <= 0.0 {
+                    return Err(
+                        builder.error(&entry, format!("radius must be positive, got {}", radius))
+                    );
+                }
+                if time0.is_nan() || time1.is_nan() || time0 >= time1 {
+                    return Err(builder.error(
+                        &entry,
+                        format!("time0 must come before time1, got {} and {}", time0, time1),
+                    ));
+                }
+                shapes.push(Box::new(MovingSphere::new(
+                    vec3(center0),
+                    vec3(center1),
+                    time0,
+                    time1,
+                    radius,
+                    material(material_name)?,
+                )));
+            }
+            ObjectDesc::Triangle {
+                vertices,
+                ref material_name,
+                ..
+            } => {
+                shapes.push(Box::new(Triangle::new(
+                    vec3(vertices[0]),
+                    vec3(vertices[1]),
+                    vec3(vertices[2]),
+                    material(material_name)?,
+                )));
+            }
+            ObjectDesc::XyRect {
+                x,
+                y,
+                z,
+                flip,
+                ref material_name,
+                ..
+            } => {
+                let ((x0, x1), (y0, y1)) = (range("x", x)?, range("y", y)?);
+                let rect = XyRect::new(x0, x1, y0, y1, z, material(material_name)?);
+                shapes.push(Box::new(if flip { rect.flipped() } else { rect }));
+            }
+            ObjectDesc::XzRect {
+                x,
+                z,
+                y,
+                flip,
+                ref material_name,
+                ..
+            } => {
+                let ((x0, x1), (z0, z1)) = (range("x", x)?, range("z", z)?);
+                let rect = XzRect::new(x0, x1, z0, z1, y, material(material_name)?);
+                shapes.push(Box::new(if flip { rect.flipped() } else { rect }));
+            }
+            ObjectDesc::YzRect {
+                y,
+                z,
+                x,
+                flip,
+                ref material_name,
+                ..
+            } => {
+                let ((y0, y1), (z0, z1)) = (range("y", y)?, range("z", z)?);
+                let rect = YzRect::new(y0, y1, z0, z1, x, material(material_name)?);
+                shapes.push(Box::new(if flip { rect.flipped() } else { rect }));
+            }
+            ObjectDesc::Box {
+                min,
+                max,
+                ref material_name,
+                ..
+            } => {
+                shapes.push(Box::new(BoxShape::new(
+                    vec3(min),
+                    vec3(max),
+                    material(material_name)?,
+                )));
+            }
+            ObjectDesc::Plane {
+                point,
+                normal,
+                ref material_name,
+                ..
+            } => {
+                let length = vec3(normal).length();
+                if !length.is_finite() || length == 0.0 {
+                    return Err(builder.error(
+                        &entry,
+                        format!("normal must be a nonzero vector, got {:?}", normal),
+                    ));
+                }
+                shapes.push(Box::new(Plane::new(
+                    vec3(point),
+                    vec3(normal),
+                    material(material_name)?,
+                )));
+            }
+            ObjectDesc::Mesh {
+                file: ref mesh_file,
+                ref material_name,
+                ..
+            } => {
+                // instances of the same file share its meshes
+                let key = (mesh_file.clone(), material_name.clone());
+                if !loaded_meshes.contains_key(&key) {
+                    let default_material = match *material_name {
+                        Some(ref name) => material(name)?,
+                        None => Arc::new(Material::Lambertian(Lambertian::new(Vec3::new(
+                            0.8, 0.8, 0.8,
+                        )))),
+                    };
+                    // parse errors already point into the obj file
+                    let meshes = obj::load_obj(base_dir.join(mesh_file), default_material)
+                        .map_err(|e| match e {
+                            Error::Io(err) => builder
+                                .error(&entry, format!("can't read '{}': {}", mesh_file, err)),
+                            e => e,
+                        })?;
+                    loaded_meshes.insert(
+                        key.clone(),
+                        meshes.into_iter().map(|mesh| Arc::new(mesh.mesh)).collect(),
+                    );
+                }
+                for mesh in &loaded_meshes[&key] {
+                    shapes.push(Box::new(mesh.clone()));
+                }
+            }
+        }
+
+        let start = builder.transform(&entry, object.transform())?;
+        let end = match object.transform_end() {
+            Some(descs) => Some(
+                builder
+                    .transform(&entry, descs)?
+                    .unwrap_or_else(Transform::identity),
+            ),
+            None => None,
+        };
+        match (start, end) {
+            (None, None) => list.extend(shapes),
+            (start, end) => {
+                let start = start.unwrap_or_else(Transform::identity);
+                let motion = AnimatedTransform::new(start, end.unwrap_or(start), 0.0, 1.0);
+                for shape in shapes {
+                    list.push(Box::new(Instance::animated(Arc::from(shape), motion)));
+                }
+            }
+        }
+    }
+
+    let camera_desc = &desc.camera;
+    let (width, height) = (desc.image.width, desc.image.height);
+    if width == 0 || height == 0 {
+        return Err(builder.error("image", format!("image size {}x{} is empty", width, height)));
+    }
+    let [shutter_open, shutter_close] = camera_desc.shutter;
+    if !(shutter_open.is_finite() && shutter_close.is_finite() && shutter_open <= shutter_close) {
+        return Err(builder.error(
+            "camera",
+            format!(
+                "shutter [{}, {}] must open before it closes",
+                shutter_open, shutter_close
+            ),
+        ));
+    }
+    let look_from = vec3(camera_desc.look_from);
+    let look_at = vec3(camera_desc.look_at);
+    let camera_params = CameraParams {
+        look_from,
+        look_at,
+        vup: vec3(camera_desc.vup),
+        vfov: camera_desc.vfov,
+        aperture: camera_desc.aperture,
+        focus_dist: camera_desc
+            .focus_dist
+            .unwrap_or_else(|| (look_from - look_at).length()),
+        shutter_open,
+        shutter_close,
+    };
+
+    Ok(Scene::new(
+        ReflexibleList::new(list),
+        camera_params,
+        desc.render.to_settings(),
+        width,
+        height,
+    ))
+}
+
+struct Builder<'a> {
+    file: &'a str,
+    base_dir: &'a Path,
+    textures: &'a BTreeMap<String, TextureDesc>,
+    resolved_textures: HashMap<String, Texture>,
+    // names of the textures being resolved, to catch checkers referring to themselves
+    resolving: Vec<String>,
+}
+
+impl<'a> Builder<'a> {
+    fn error(&self, entry: &str, message: String) -> Error {
+        Error::Scene {
+            file: self.file.to_string(),
+            entry: entry.to_string(),
+            message,
+        }
+    }
+
+    fn material(&mut self, entry: &str, desc: &MaterialDesc) -> Result<Material, Error> {
+        Ok(match *desc {
+            MaterialDesc::Lambertian { ref albedo } => {
+                Material::Lambertian(Lambertian::textured(self.texture_ref(entry, albedo)?))
+            }
+            MaterialDesc::Metal {
+                ref albedo,
+                fuzz,
+                phong,
+            } => {
+                // above 1 is clamped, but a negative lobe makes no sense
+                if fuzz.is_nan() || fuzz < 0.0 {
+                    return Err(
+                        self.error(entry, format!("metal fuzz can't be negative, got {}", fuzz))
+                    );
+                }
+                let metal = Metal::textured(self.texture_ref(entry, albedo)?, fuzz);
+                Material::Metal(if phong {
+                    metal.with_phong_lobe()
+                } else {
+                    metal
+                })
+            }
+            MaterialDesc::Dielectric { ref_idx } => {
+                if !ref_idx.is_finite() || ref_idx <= 0.0 {
+                    return Err(self.error(
+                        entry,
+                        format!("refractive index must be positive, got {}", ref_idx),
+                    ));
+                }
+                Material::Dielectric(Dielectric::new(ref_idx))
+            }
+            MaterialDesc::DiffuseLight { emit } => {
+                Material::DiffuseLight(DiffuseLight::new(vec3(emit)))
+            }
+        })
+    }
+
+    // the transforms in the order they are listed, None if there are none
+    fn transform(&self, entry: &str, descs: &[TransformDesc]) -> Result<Option<Transform>, Error> {
+        let mut result: Option<Transform> = None;
+
+        for desc in descs {
+            let transform = match *desc {
+                TransformDesc::Translate(offset) => Transform::translate(vec3(offset)),
+                TransformDesc::Scale(ref scale) => {
+                    let factors = match *scale {
+                        ScaleDesc::Uniform(s) => [s, s, s],
+                        ScaleDesc::Axes(s) => s,
+                    };
+                    Transform::scale(vec3(factors)).ok_or_else(|| {
+                        self.error(
+                            entry,
+                            format!("scale factors must be nonzero, got {:?}", factors),
+                        )
+                    })?
+                }
+                TransformDesc::RotateX(degrees) => Transform::rotate_x(degrees),
+                TransformDesc::RotateY(degrees) => Transform::rotate_y(degrees),
+                TransformDesc::RotateZ(degrees) => Transform::rotate_z(degrees),
+                TransformDesc::Rotate { axis, angle } => {
+                    let length = vec3(axis).length();
+                    if !length.is_finite() || length == 0.0 {
+                        return Err(self.error(
+                            entry,
+                            format!("rotation axis must be a nonzero vector, got {:?}", axis),
+                        ));
+                    }
+                    Transform::rotate(vec3(axis), angle)
+                }
+                TransformDesc::Matrix(m) => {
+                    if m[3] != [0.0, 0.0, 0.0, 1.0] {
+                        return Err(self.error(
+                            entry,
+                            format!("the matrix's last row must be [0, 0, 0, 1], got {:?}", m[3]),
+                        ));
+                    }
+                    Transform::from_matrix(Matrix4::new(m))
+                        .ok_or_else(|| self.error(entry, "matrix can't be inverted".to_string()))?
+                }
+            };
+            result = Some(match result {
+                Some(result) => result.then(&transform),
+                None => transform,
+            });
+        }
+
+        Ok(result)
+    }
+
+    fn texture_ref(&mut self, entry: &str, desc: &TextureRef) -> Result<Texture, Error> {
+        match *desc {
+            TextureRef::Color(color) => Ok(Texture::solid(vec3(color))),
+            TextureRef::Name(ref name) => self.named_texture(entry, name),
+        }
+    }
+
+    fn named_texture(&mut self, entry: &str, name: &str) -> Result<Texture, Error> {
+        if let Some(texture) = self.resolved_textures.get(name) {
+            return Ok(texture.clone());
+        }
+        let desc = match self.textures.get(name) {
+            Some(desc) => desc,
+            None => return Err(self.error(entry, format!("unknown texture '{}'", name))),
+        };
+        if self.resolving.iter().any(|n| n == name) {
+            return Err(self.error(entry, format!("texture '{}' refers to itself", name)));
+        }
+
+        self.resolving.push(name.to_string());
+        let entry = format!("textures.{}", name);
+        let texture = match *desc {
+            TextureDesc::Solid { color } => Texture::solid(vec3(color)),
+            TextureDesc::Checker {
+                ref even,
+                ref odd,
+                scale,
+            } => {
+                if !scale.is_finite() || scale <= 0.0 {
+                    return Err(self.error(
+                        &entry,
+                        format!("checker scale must be positive, got {}", scale),
+                    ));
+                }
+                Texture::Checker(Checker::new(
+                    self.texture_ref(&entry, even)?,
+                    self.texture_ref(&entry, odd)?,
+                    scale,
+                ))
+            }
+            TextureDesc::Image { ref file } => {
+                let texture = ImageTexture::open(self.base_dir.join(file))
+                    .map_err(|e| self.error(&entry, format!("can't load '{}': {}", file, e)))?;
+                Texture::Image(texture)
+            }
+            TextureDesc::Noise { scale, style, seed } => {
+                Texture::Noise(NoiseTexture::with_seed(scale, style.into(), seed))
+            }
+        };
+        self.resolving.pop();
+
+        self.resolved_textures
+            .insert(name.to_string(), texture.clone());
+        Ok(texture)
+    }
+}
+
+fn toml_error(file: &str, source: &str, err: toml::de::Error) -> Error {
+    match err.span() {
+        Some(span) => Error::Parse {
+            file: file.to_string(),
+            line: source[..span.start].matches('\n').count() + 1,
+            message: err.message().to_string(),
+        },
+        None => Error::Scene {
+            file: file.to_string(),
+            entry: "scene".to_string(),
+            message: err.message().to_string(),
+        },
+    }
+}
+
+fn vec3(v: [f64; 3]) -> Vec3 {
+    Vec3::new(v[0], v[1], v[2])
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneDesc {
+    camera: CameraDesc,
+    #[serde(default)]
+    image: ImageDesc,
+    #[serde(default)]
+    render: RenderDesc,
+    #[serde(default)]
+    textures: BTreeMap<String, TextureDesc>,
+    #[serde(default)]
+    materials: BTreeMap<String, MaterialDesc>,
+    #[serde(default)]
+    objects: Vec<ObjectDesc>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraDesc {
+    look_from: [f64; 3],
+    look_at: [f64; 3],
+    #[serde(default = "default_vup")]
+    vup: [f64; 3],
+    vfov: f64,
+    #[serde(default)]
+    aperture: f64,
+    // defaults to the distance between look_from and look_at
+    focus_dist: Option<f64>,
+    // open and close times, objects move between 0 and 1
+    #[serde(default)]
+    shutter: [f64; 2],
+}
+
+fn default_vup() -> [f64; 3] {
+    [0.0, 1.0, 0.0]
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ImageDesc {
+    width: u32,
+    height: u32,
+}
+
+impl Default for ImageDesc {
+    fn default() -> ImageDesc {
+        ImageDesc {
+            width: 1920,
+            height: 1080,
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct RenderDesc {
+    samples: Option<u32>,
+    adaptive: Option<AdaptiveDesc>,
+    progressive: Option<ProgressiveDesc>,
+    integrator: Option<IntegratorDesc>,
+    max_depth: Option<u32>,
+    min_depth: Option<u32>,
+    t_min: Option<f64>,
+    gamma: Option<GammaDesc>,
+    threads: Option<usize>,
+    seed: Option<u64>,
+    background: Option<BackgroundDesc>,
+    tone_map: Option<ToneMapDesc>,
+    exposure: Option<f64>,
+    dither: Option<bool>,
+    #[serde(default)]
+    aovs: Vec<AovDesc>,
+}
+
+impl RenderDesc {
+    fn to_settings(&self) -> RenderSettings {
+        let defaults = RenderSettings::default();
+
+        RenderSettings {
+            samples: self.samples.unwrap_or(defaults.samples),
+            adaptive: self.adaptive.as_ref().map(|desc| {
+                let mut adaptive = AdaptiveSampling::new(desc.threshold);
+                if let Some(min_samples) = desc.min_samples {
+                    adaptive.min_samples = min_samples;
+                }
+                adaptive
+            }),
+            progressive: self.progressive.as_ref().map(|desc| Progressive {
+                snapshot_passes: desc.snapshot_passes,
+                snapshot_interval: desc.snapshot_seconds.map(Duration::from_secs_f64),
+                time_budget: desc.time_limit.map(Duration::from_secs_f64),
+            }),
+            checkpoint_interval: defaults.checkpoint_interval,
+            integrator: match self.integrator {
+                Some(IntegratorDesc::Path) => BuiltinIntegrator::Path(PathTracer),
+                Some(IntegratorDesc::AmbientOcclusion { distance }) => {
+                    let ao = AmbientOcclusion::new();
+                    BuiltinIntegrator::AmbientOcclusion(match distance {
+                        Some(distance) => ao.with_distance(distance),
+                        None => ao,
+                    })
+                }
+                Some(IntegratorDesc::Normal) => BuiltinIntegrator::Debug(DebugView::Normal),
+                Some(IntegratorDesc::Albedo) => BuiltinIntegrator::Debug(DebugView::Albedo),
+                Some(IntegratorDesc::Uv) => BuiltinIntegrator::Debug(DebugView::Uv),
+                None => defaults.integrator,
+            },
+            max_depth: self.max_depth.unwrap_or(defaults.max_depth),
+            min_depth: self.min_depth.unwrap_or(defaults.min_depth),
+            t_min: self.t_min.unwrap_or(defaults.t_min),
+            threads: self.threads.unwrap_or(defaults.threads),
+            seed: self.seed.unwrap_or(defaults.seed),
+            background: match self.background {
+                Some(BackgroundDesc::Sky) => Background::sky(),
+                Some(BackgroundDesc::Gradient { bottom, top }) => Background::Gradient {
+                    bottom: vec3(bottom),
+                    top: vec3(top),
+                },
+                Some(BackgroundDesc::Solid { color }) => Background::Solid(vec3(color)),
+                None => defaults.background,
+            },
+            tone_mapping: ToneMapping {
+                tone_map: match self.tone_map {
+                    Some(ToneMapDesc::Clamp) => ToneMap::Clamp,
+                    Some(ToneMapDesc::Reinhard) => ToneMap::Reinhard,
+                    Some(ToneMapDesc::ReinhardExtended { white }) => {
+                        ToneMap::ReinhardExtended { white }
+                    }
+                    Some(ToneMapDesc::Aces) => ToneMap::Aces,
+                    Some(ToneMapDesc::Hable) => ToneMap::Hable,
+                    None => defaults.tone_mapping.tone_map,
+                },
+                exposure: self.exposure.unwrap_or(defaults.tone_mapping.exposure),
+                transfer: match self.gamma {
+                    Some(GammaDesc::Exponent(gamma)) => Transfer::Gamma(gamma),
+                    Some(GammaDesc::Srgb(_)) => Transfer::Srgb,
+                    None => defaults.tone_mapping.transfer,
+                },
+                dither: self.dither.unwrap_or(defaults.tone_mapping.dither),
+            },
+            aovs: self
+                .aovs
+                .iter()
+                .fold(defaults.aovs, |set, aov| set.with(Aov::from(*aov))),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AdaptiveDesc {
+    threshold: f64,
+    min_samples: Option<u32>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProgressiveDesc {
+    snapshot_passes: Option<u32>,
+    // seconds
+    snapshot_seconds: Option<f64>,
+    time_limit: Option<f64>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum AovDesc {
+    Depth,
+    Normal,
+    Albedo,
+    Position,
+    ObjectId,
+    MaterialId,
+    SampleCount,
+}
+
+impl From<AovDesc> for Aov {
+    fn from(aov: AovDesc) -> Aov {
+        match aov {
+            AovDesc::Depth => Aov::Depth,
+            AovDesc::Normal => Aov::Normal,
+            AovDesc::Albedo => Aov::Albedo,
+            AovDesc::Position => Aov::Position,
+            AovDesc::ObjectId => Aov::ObjectId,
+            AovDesc::MaterialId => Aov::MaterialId,
+            AovDesc::SampleCount => Aov::SampleCount,
+        }
+    }
+}
+
+// either a plain exponent or "srgb" for the srgb transfer curve
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum GammaDesc {
+    Exponent(f64),
+    Srgb(SrgbDesc),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SrgbDesc {
+    Srgb,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum IntegratorDesc {
+    Path,
+    // without a distance it looks as far as the camera focuses
+    AmbientOcclusion {
+        #[serde(default)]
+        distance: Option<f64>,
+    },
+    Normal,
+    Albedo,
+    Uv,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum ToneMapDesc {
+    Clamp,
+    Reinhard,
+    ReinhardExtended {
+        #[serde(default = "default_white")]
+        white: f64,
+    },
+    Aces,
+    Hable,
+}
+
+fn default_white() -> f64 {
+    tonemap::DEFAULT_WHITE
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum BackgroundDesc {
+    Sky,
+    Gradient { bottom: [f64; 3], top: [f64; 3] },
+    Solid { color: [f64; 3] },
+}
+
+// either an inline color or the name of a texture
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TextureRef {
+    Color([f64; 3]),
+    Name(String),
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum TextureDesc {
+    Solid {
+        color: [f64; 3],
+    },
+    Checker {
+        even: TextureRef,
+        odd: TextureRef,
+        #[serde(default = "default_scale")]
+        scale: f64,
+    },
+    Image {
+        file: String,
+    },
+    Noise {
+        #[serde(default = "default_scale")]
+        scale: f64,
+        #[serde(default)]
+        style: NoiseStyleDesc,
+        #[serde(default)]
+        seed: u64,
+    },
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+enum NoiseStyleDesc {
+    #[default]
+    Noise,
+    Turbulence,
+    Marble,
+}
+
+impl From<NoiseStyleDesc> for NoiseStyle {
+    fn from(style: NoiseStyleDesc) -> NoiseStyle {
+        match style {
+            NoiseStyleDesc::Noise => NoiseStyle::Noise,
+            NoiseStyleDesc::Turbulence => NoiseStyle::Turbulence,
+            NoiseStyleDesc::Marble => NoiseStyle::Marble,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum MaterialDesc {
+    Lambertian {
+        albedo: TextureRef,
+    },
+    Metal {
+        albedo: TextureRef,
+        #[serde(default)]
+        fuzz: f64,
+        #[serde(default)]
+        phong: bool,
+    },
+    Dielectric {
+        ref_idx: f64,
+    },
+    DiffuseLight {
+        emit: [f64; 3],
+    },
+}
+
+// every object can be placed by a `transform` and moved to a `transform_end` by time 1
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum ObjectDesc {
+    Sphere {
+        center: [f64; 3],
+        radius: f64,
+        #[serde(rename = "material")]
+        material_name: String,
+        #[serde(default)]
+        transform: Vec<TransformDesc>,
+        transform_end: Option<Vec<TransformDesc>>,
+    },
+    // at center0 at time0 and center1 at time1
+    MovingSphere {
+        center0: [f64; 3],
+        center1: [f64; 3],
+        #[serde(default)]
+        time0: f64,
+        #[serde(default = "default_time1")]
+        time1: f64,
+        radius: f64,
+        #[serde(rename = "material")]
+        material_name: String,
+        #[serde(default)]
+        transform: Vec<TransformDesc>,
+        transform_end: Option<Vec<TransformDesc>>,
+    },
+    Triangle {
+        vertices: [[f64; 3]; 3],
+        #[serde(rename = "material")]
+        material_name: String,
+        #[serde(default)]
+        transform: Vec<TransformDesc>,
+        transform_end: Option<Vec<TransformDesc>>,
+    },
+    // a rectangle facing +z, or -z when flipped
+    XyRect {
+        x: [f64; 2],
+        y: [f64; 2],
+        z: f64,
+        #[serde(default)]
+        flip: bool,
+        #[serde(rename = "material")]
+        material_name: String,
+        #[serde(default)]
+        transform: Vec<TransformDesc>,
+        transform_end: Option<Vec<TransformDesc>>,
+    },
+    XzRect {
+        x: [f64; 2],
+        z: [f64; 2],
+        y: f64,
+        #[serde(default)]
+        flip: bool,
+        #[serde(rename = "material")]
+        material_name: String,
+        #[serde(default)]
+        transform: Vec<TransformDesc>,
+        transform_end: Option<Vec<TransformDesc>>,
+    },
+    YzRect {
+        y: [f64; 2],
+        z: [f64; 2],
+        x: f64,
+        #[serde(default)]
+        flip: bool,
+        #[serde(rename = "material")]
+        material_name: String,
+        #[serde(default)]
+        transform: Vec<TransformDesc>,
+        transform_end: Option<Vec<TransformDesc>>,
+    },
+    Box {
+        min: [f64; 3],
+        max: [f64; 3],
+        #[serde(rename = "material")]
+        material_name: String,
+        #[serde(default)]
+        transform: Vec<TransformDesc>,
+        transform_end: Option<Vec<TransformDesc>>,
+    },
+    Plane {
+        point: [f64; 3],
+        normal: [f64; 3],
+        #[serde(rename = "material")]
+        material_name: String,
+        #[serde(default)]
+        transform: Vec<TransformDesc>,
+        transform_end: Option<Vec<TransformDesc>>,
+    },
+    Mesh {
+        file: String,
+        // used for faces without an mtl material
+        #[serde(rename = "material")]
+        material_name: Option<String>,
+        #[serde(default)]
+        transform: Vec<TransformDesc>,
+        transform_end: Option<Vec<TransformDesc>>,
+    },
+}
+
+// applied in order, `transform = [{ scale = 2.0 }, { rotate_y = 45.0 }]` scales first
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum TransformDesc {
+    Translate([f64; 3]),
+    Scale(ScaleDesc),
+    RotateX(f64),
+    RotateY(f64),
+    RotateZ(f64),
+    Rotate { axis: [f64; 3], angle: f64 },
+    // row major
+    Matrix([[f64; 4]; 4]),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ScaleDesc {
+    Uniform(f64),
+    Axes([f64; 3]),
+}
+
+fn default_time1() -> f64 {
+    1.0
+}
+
+impl ObjectDesc {
+    fn transform(&self) -> &[TransformDesc] {
+        match *self {
+            ObjectDesc::Sphere { ref transform, .. }
+            | ObjectDesc::MovingSphere { ref transform, .. }
+            | ObjectDesc::Triangle { ref transform, .. }
+            | ObjectDesc::XyRect { ref transform, .. }
+            | ObjectDesc::XzRect { ref transform, .. }
+            | ObjectDesc::YzRect { ref transform, .. }
+            | ObjectDesc::Box { ref transform, .. }
+            | ObjectDesc::Plane { ref transform, .. }
+            | ObjectDesc::Mesh { ref transform, .. } => transform,
+        }
+    }
+
+    fn transform_end(&self) -> Option<&[TransformDesc]> {
+        match *self {
+            ObjectDesc::Sphere {
+                ref transform_end, ..
+            }
+            | ObjectDesc::MovingSphere {
+                ref transform_end, ..
+            }
+            | ObjectDesc::Triangle {
+                ref transform_end, ..
+            }
+            | ObjectDesc::XyRect {
+                ref transform_end, ..
+            }
+            | ObjectDesc::XzRect {
+                ref transform_end, ..
+            }
+            | ObjectDesc::YzRect {
+                ref transform_end, ..
+            }
+            | ObjectDesc::Box {
+                ref transform_end, ..
+            }
+            | ObjectDesc::Plane {
+                ref transform_end, ..
+            }
+            | ObjectDesc::Mesh {
+                ref transform_end, ..
+            } => transform_end.as_deref(),
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match *self {
+            ObjectDesc::Sphere { .. } => "sphere",
+            ObjectDesc::MovingSphere { .. } => "moving_sphere",
+            ObjectDesc::Triangle { .. } => "triangle",
+            ObjectDesc::XyRect { .. } => "xy_rect",
+            ObjectDesc::XzRect { .. } => "xz_rect",
+            ObjectDesc::YzRect { .. } => "yz_rect",
+            ObjectDesc::Box { .. } => "box",
+            ObjectDesc::Plane { .. } => "plane",
+            ObjectDesc::Mesh { .. } => "mesh",
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aov::MaterialIds;
+    use crate::ray::Ray;
+
+    const SCENE: &str = r#"
+        [camera]
+        look_from = [0.0, 3.0, 7.0]
+        look_at = [0.0, 1.5, -1.0]
+        vfov = 30.0
+
+        [image]
+        width = 200
+        height = 100
+
+        [render]
+        samples = 8
+        min_depth = 3
+        background = { type = "solid", color = [0.0, 0.0, 0.0] }
+
+        [textures.floor]
+        type = "checker"
+        even = [1.0, 1.0, 1.0]
+        odd = "dark"
+
+        [textures.dark]
+        type = "solid"
+        color = [0.1, 0.1, 0.1]
+
+        [materials.floor]
+        type = "lambertian"
+        albedo = "floor"
+
+        [materials.gold]
+        type = "metal"
+        albedo = [0.8, 0.6, 0.2]
+
+        [[objects]]
+        type = "sphere"
+        center = [0.0, -100.0, -1.0]
+        radius = 100.0
+        material = "floor"
+
+        [[objects]]
+        type = "sphere"
+        center = [0.0, 1.0, -1.0]
+        radius = 1.0
+        material = "gold"
+    "#;
+
+    fn scene_error(source: &str) -> Error {
+        match parse_scene(source, "test.toml", Path::new("")) {
+            Ok(_) => panic!("expected an error"),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let scene = parse_scene(SCENE, "test.toml", Path::new("")).unwrap();
+
+        assert_eq!((scene.width, scene.height), (200, 100));
+        assert_eq!(scene.settings.samples, 8);
+        assert_eq!(scene.settings.max_depth, 50);
+        assert_eq!(scene.settings.min_depth, 3);
+        assert_eq!(scene.settings.background, Background::black());
+        assert_eq!(scene.world.list.len(), 2);
+
+        let r = Ray::new(Vec3::new(0.0, 1.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        let rec = scene.world.hit(&r, 0.001, f64::MAX).unwrap();
+        assert!((rec.t - 5.0).abs() < 1e-9);
+        assert_eq!(
+            *rec.material,
+            Material::Metal(Metal::new(Vec3::new(0.8, 0.6, 0.2), 0.0))
+        );
+    }
+
+    #[test]
+    fn tone_mapping() {
+        let source = SCENE.replace(
+            "samples = 8",
+            "samples = 8\ngamma = \"srgb\"\nexposure = -1.0\ntone_map = { type = \"reinhard_extended\" }",
+        );
+        let tone_mapping = parse_scene(&source, "test.toml", Path::new(""))
+            .unwrap()
+            .settings
+            .tone_mapping;
+
+        assert_eq!(tone_mapping.transfer, Transfer::Srgb);
+        assert_eq!(tone_mapping.exposure, -1.0);
+        assert_eq!(
+            tone_mapping.tone_map,
+            ToneMap::ReinhardExtended {
+                white: tonemap::DEFAULT_WHITE
+            }
+        );
+        assert!(!tone_mapping.dither);
+
+        let source = SCENE.replace("samples = 8", "samples = 8\ngamma = \"linear\"");
+        match scene_error(&source) {
+            Error::Parse { line, .. } => assert_eq!(line, 13),
+            e => panic!("unexpected {}", e),
+        }
+    }
+
+    #[test]
+    fn integrators() {
+        let settings = parse_scene(SCENE, "test.toml", Path::new(""))
+            .unwrap()
+            .settings;
+        assert_eq!(settings.integrator, BuiltinIntegrator::Path(PathTracer));
+
+        let source = SCENE.replace(
+            "samples = 8",
+            "samples = 8\nintegrator = { type = \"ambient_occlusion\", distance = 2.5 }",
+        );
+        let settings = parse_scene(&source, "test.toml", Path::new(""))
+            .unwrap()
+            .settings;
+        assert_eq!(
+            settings.integrator,
+            BuiltinIntegrator::AmbientOcclusion(AmbientOcclusion::new().with_distance(2.5))
+        );
+
+        let source = SCENE.replace(
+            "samples = 8",
+            "samples = 8\nintegrator = { type = \"ambient_occlusion\" }",
+        );
+        let settings = parse_scene(&source, "test.toml", Path::new(""))
+            .unwrap()
+            .settings;
+        assert_eq!(
+            settings.integrator,
+            BuiltinIntegrator::AmbientOcclusion(AmbientOcclusion::new())
+        );
+
+        let source = SCENE.replace("samples = 8", "samples = 8\nintegrator = { type = \"uv\" }");
+        let settings = parse_scene(&source, "test.toml", Path::new(""))
+            .unwrap()
+            .settings;
+        assert_eq!(settings.integrator, BuiltinIntegrator::Debug(DebugView::Uv));
+
+        let source = SCENE.replace(
+            "samples = 8",
+            "samples = 8\nintegrator = { type = \"light_tracing\" }",
+        );
+        match scene_error(&source) {
+            Error::Parse { line, .. } => assert_eq!(line, 13),
+            e => panic!("unexpected {}", e),
+        }
+    }
+
+    #[test]
+    fn shapes() {
+        let source = SCENE.replace(
+            "center = [0.0, -100.0, -1.0]\n        radius = 100.0",
+            "point = [0.0, -0.5, 0.0]\n        normal = [0.0, 1.0, 0.0]",
+        );
+        let source = source.replacen("type = \"sphere\"", "type = \"plane\"", 1)
+            + r#"
+        [[objects]]
+        type = "box"
+        min = [2.0, 0.0, -1.0]
+        max = [3.0, 1.0, 0.0]
+        material = "gold"
+
+        [[objects]]
+        type = "xz_rect"
+        x = [-1.0, 1.0]
+        z = [-1.0, 1.0]
+        y = 4.0
+        flip = true
+        material = "floor"
+        "#;
+        let scene = parse_scene(&source, "test.toml", Path::new("")).unwrap();
+        assert_eq!(scene.world.list.len(), 4);
+
+        let down = |x, z| Ray::new(Vec3::new(x, 10.0, z), Vec3::new(0.0, -1.0, 0.0));
+        let hit = |r: &Ray| scene.world.hit(r, 0.001, f64::MAX).unwrap();
+        // the flipped rectangle faces down, away from the ray
+        assert!(!hit(&down(0.0, 0.0)).front_face);
+        assert_eq!(hit(&down(2.5, -0.5)).p, Vec3::new(2.5, 1.0, -0.5));
+        assert_eq!(hit(&down(10.0, 10.0)).p, Vec3::new(10.0, -0.5, 10.0));
+
+        let source = source.replace("x = [-1.0, 1.0]", "x = [1.0, -1.0]");
+        match scene_error(&source) {
+            Error::Scene { entry, message, .. } => {
+                assert_eq!(entry, "objects[3] (xz_rect)");
+                assert_eq!(message, "x range [1, -1] is empty");
+            }
+            e => panic!("unexpected {}", e),
+        }
+    }
+
+    #[test]
+    fn shared_materials() {
+        let source = SCENE.to_string()
+            + r#"
+        [materials.light]
+        type = "diffuse_light"
+        emit = [4.0, 4.0, 4.0]
+
+        [[objects]]
+        type = "sphere"
+        center = [3.0, 1.0, -1.0]
+        radius = 1.0
+        material = "gold"
+
+        [[objects]]
+        type = "sphere"
+        center = [-3.0, 1.0, -1.0]
+        radius = 1.0
+        material = "light"
+
+        [[objects]]
+        type = "sphere"
+        center = [-6.0, 1.0, -1.0]
+        radius = 1.0
+        material = "light"
+        "#;
+        let scene = parse_scene(&source, "test.toml", Path::new("")).unwrap();
+        let ids = MaterialIds::new(&scene.world);
+        let id = |x: f64| {
+            let r = Ray::new(Vec3::new(x, 1.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+            ids.get(scene.world.hit(&r, 0.001, f64::MAX).unwrap().material)
+        };
+
+        // both gold spheres, whatever the camera sees first
+        assert_eq!((id(0.0), id(3.0)), (2, 2));
+        // lights are told apart by their material, so each gets its own
+        assert_eq!((id(-3.0), id(-6.0)), (3, 4));
+    }
+
+    #[test]
+    fn transforms() {
+        let source = SCENE.replace(
+            "material = \"gold\"",
+            "material = \"gold\"\ntransform = [{ scale = [2.0, 1.0, 1.0] }, { translate = [1.0, 0.0, 0.0] }]",
+        );
+        let scene = parse_scene(&source, "test.toml", Path::new("")).unwrap();
+
+        let r = Ray::new(Vec3::new(10.0, 1.0, -1.0), Vec3::new(-1.0, 0.0, 0.0));
+        let rec = scene.world.hit(&r, 0.001, f64::MAX).unwrap();
+        assert!((rec.t - 7.0).abs() < 1e-9);
+        assert!((rec.normal - Vec3::new(1.0, 0.0, 0.0)).length() < 1e-9);
+
+        let source = SCENE.replace(
+            "material = \"gold\"",
+            "material = \"gold\"\ntransform = [{ rotate_y = 30.0 }, { scale = 0.0 }]",
+        );
+        match scene_error(&source) {
+            Error::Scene { entry, message, .. } => {
+                assert_eq!(entry, "objects[1] (sphere)");
+                assert_eq!(
+                    message,
+                    "scale factors must be nonzero, got [0.0, 0.0, 0.0]"
+                );
+            }
+            e => panic!("unexpected {}", e),
+        }
+
+        let source = SCENE.replace(
+            "material = \"gold\"",
+            "material = \"gold\"\ntransform = [{ matrix = [[1.0, 0.0, 0.0, 0.0], [0.0, 1.0, 0.0, 0.0], [0.0, 0.0, 1.0, 0.0], [0.0, 0.0, 1.0, 0.0]] }]",
+        );
+        match scene_error(&source) {
+            Error::Scene { entry, message, .. } => {
+                assert_eq!(entry, "objects[1] (sphere)");
+                assert_eq!(
+                    message,
+                    "the matrix's last row must be [0, 0, 0, 1], got [0.0, 0.0, 1.0, 0.0]"
+                );
+            }
+            e => panic!("unexpected {}", e),
+        }
+    }
+
+    #[test]
+    fn unknown_material() {
+        let source = SCENE.replace("material = \"gold\"", "material = \"silver\"");
+
+        match scene_error(&source) {
+            Error::Scene { entry, message, .. } => {
+                assert_eq!(entry, "objects[1] (sphere)");
+                assert_eq!(message, "unknown material 'silver'");
+            }
+            e => panic!("unexpected {}", e),
+        }
+    }
+
+    #[test]
+    fn texture_cycle() {
+        let source = SCENE.replace("odd = \"dark\"", "odd = \"floor\"");
+
+        match scene_error(&source) {
+            Error::Scene { entry, message, .. } => {
+                assert_eq!(entry, "textures.floor");
+                assert_eq!(message, "texture 'floor' refers to itself");
+            }
+            e => panic!("unexpected {}", e),
+        }
+    }
+
+    #[test]
+    fn checker_scale() {
+        for scale in &["0.0", "-2.0", "inf"] {
+            let source = SCENE.replace(
+                "odd = \"dark\"",
+                &format!("odd = \"dark\"\nscale = {}", scale),
+            );
+            match scene_error(&source) {
+                Error::Scene { entry, message, .. } => {
+                    assert_eq!(entry, "textures.floor");
+                    assert!(message.starts_with("checker scale must be positive"));
+                }
+                e => panic!("unexpected {}", e),
+            }
+        }
+    }
+
+    #[test]
+    fn material_parameters() {
+        for fuzz in &["-0.1", "nan"] {
+            let source = SCENE.replace(
+                "albedo = [0.8, 0.6, 0.2]",
+                &format!("albedo = [0.8, 0.6, 0.2]\nfuzz = {}", fuzz),
+            );
+            match scene_error(&source) {
+                Error::Scene { entry, message, .. } => {
+                    assert_eq!(entry, "materials.gold");
+                    assert!(message.starts_with("metal fuzz can't be negative"));
+                }
+                e => panic!("unexpected {}", e),
+            }
+        }
+
+        for ref_idx in &["0.0", "-1.5", "nan"] {
+            let source = SCENE.replace(
+                "type = \"metal\"\n        albedo = [0.8, 0.6, 0.2]",
+                &format!("type = \"dielectric\"\nref_idx = {}", ref_idx),
+            );
+            match scene_error(&source) {
+                Error::Scene { entry, message, .. } => {
+                    assert_eq!(entry, "materials.gold");
+                    assert!(message.starts_with("refractive index must be positive"));
+                }
+                e => panic!("unexpected {}", e),
+            }
+        }
+
+        let source = SCENE.replace(
+            "type = \"metal\"\n        albedo = [0.8, 0.6, 0.2]",
+            "type = \"dielectric\"\nref_idx = 1.5",
+        );
+        parse_scene(&source, "test.toml", Path::new("")).unwrap();
+    }
+
+    #[test]
+    fn errors_carry_line_numbers() {
+        let source = "[camera]\nlook_from = [0.0, 3.0 7.0]\nlook_at = [0.0, 0.0, 0.0]\n";
+        match scene_error(source) {
+            Error::Parse { file, line, .. } => {
+                assert_eq!(file, "test.toml");
+                assert_eq!(line, 2);
+            }
+            e => panic!("unexpected {}", e),
+        }
+
+        let source = SCENE.replace("samples = 8", "samples = 8\nsample = 4");
+        match scene_error(&source) {
+            Error::Parse { line, message, .. } => {
+                assert_eq!(line, 13);
+                assert!(message.starts_with("unknown field `sample`"));
+            }
+            e => panic!("unexpected {}", e),
+        }
+    }
+}
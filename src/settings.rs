@@ -0,0 +1,98 @@
+use crate::adaptive::AdaptiveSampling;
+use crate::aov::AovSet;
+use crate::integrators::BuiltinIntegrator;
+use crate::ray::Ray;
+use crate::tonemap::ToneMapping;
+use crate::vec3::Vec3;
+
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Background {
+    // vertical blend from `bottom` to `top` based on the ray direction
+    Gradient { bottom: Vec3, top: Vec3 },
+    Solid(Vec3),
+}
+
+impl Background {
+    pub fn sky() -> Background {
+        Background::Gradient {
+            bottom: Vec3::new(1.0, 1.0, 1.0),
+            top: Vec3::new(0.5, 0.7, 1.0),
+        }
+    }
+
+    pub fn black() -> Background {
+        Background::Solid(Vec3::new(0.0, 0.0, 0.0))
+    }
+
+    pub fn color(&self, r: &Ray) -> Vec3 {
+        match *self {
+            Background::Gradient { bottom, top } => {
+                let unit_direction = Vec3::unit_vector(r.direction);
+                let t: f64 = 0.5 * (unit_direction.y + 1.0);
+
+                (1.0 - t) * bottom + t * top
+            }
+            Background::Solid(color) => color,
+        }
+    }
+}
+
+// renders one sample per pixel per pass over the whole image, so the picture can be looked
+// at while it converges. `RenderSettings::samples` is the number of passes.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Progressive {
+    // a snapshot is taken after every this many passes
+    pub snapshot_passes: Option<u32>,
+    // and once this much time has passed since the last one
+    pub snapshot_interval: Option<Duration>,
+    // stops after this long, possibly in the middle of a pass
+    pub time_budget: Option<Duration>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RenderSettings {
+    // the maximum with adaptive sampling
+    pub samples: u32,
+    pub adaptive: Option<AdaptiveSampling>,
+    pub progressive: Option<Progressive>,
+    // how often observers get a checkpoint of the samples, see `checkpoint`
+    pub checkpoint_interval: Option<Duration>,
+    // turns camera rays into colors, see `integrators`
+    pub integrator: BuiltinIntegrator,
+    pub max_depth: u32,
+    // bounces every path takes before it can be ended at random, the more likely the less
+    // light it still carries (russian roulette)
+    pub min_depth: u32,
+    // minimum hit distance, keeps scattered rays from re-hitting their origin (shadow acne)
+    pub t_min: f64,
+    // how the linear framebuffer is turned into 8-bit images
+    pub tone_mapping: ToneMapping,
+    // auxiliary buffers rendered next to the image
+    pub aovs: AovSet,
+    pub background: Background,
+    pub threads: usize,
+    // renders with the same seed, scene and settings are identical, whatever the thread count
+    pub seed: u64,
+}
+
+impl Default for RenderSettings {
+    fn default() -> RenderSettings {
+        RenderSettings {
+            samples: 100,
+            adaptive: None,
+            progressive: None,
+            checkpoint_interval: None,
+            integrator: BuiltinIntegrator::default(),
+            max_depth: 50,
+            min_depth: 5,
+            t_min: 0.001,
+            tone_mapping: ToneMapping::default(),
+            aovs: AovSet::new(),
+            background: Background::sky(),
+            threads: crate::default_threads(),
+            seed: 0,
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use super::{Texturable, Texture};
+use crate::vec3::Vec3;
+
+// 3d checker pattern in world space, `scale` is the size of one cell
+#[derive(Clone, Debug, PartialEq)]
+pub struct Checker {
+    even: Box<Texture>,
+    odd: Box<Texture>,
+    scale: f64,
+}
+
+impl Checker {
+    pub fn new(even: Texture, odd: Texture, scale: f64) -> Checker {
+        Checker {
+            even: Box::new(even),
+            odd: Box::new(odd),
+            scale,
+        }
+    }
+}
+
+impl Texturable for Checker {
+    fn value(&self, u: f64, v: f64, p: &Vec3) -> Vec3 {
+        let inv_scale = 1.0 / self.scale;
+        let cell =
+            (p.x * inv_scale).floor() + (p.y * inv_scale).floor() + (p.z * inv_scale).floor();
+
+        if cell as i64 % 2 == 0 {
+            self.even.value(u, v, p)
+        } else {
+            self.odd.value(u, v, p)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn alternates() {
+        let white = Vec3::new(1.0, 1.0, 1.0);
+        let black = Vec3::new(0.0, 0.0, 0.0);
+        let checker = Checker::new(Texture::solid(white), Texture::solid(black), 1.0);
+
+        assert_eq!(checker.value(0.0, 0.0, &Vec3::new(0.5, 0.5, 0.5)), white);
+        assert_eq!(checker.value(0.0, 0.0, &Vec3::new(1.5, 0.5, 0.5)), black);
+        assert_eq!(checker.value(0.0, 0.0, &Vec3::new(-0.5, 0.5, 0.5)), black);
+        assert_eq!(checker.value(0.0, 0.0, &Vec3::new(1.5, -0.5, 0.5)), white);
+    }
+}
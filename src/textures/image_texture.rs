@@ -0,0 +1,74 @@
+use super::Texturable;
+use crate::error::Error;
+use crate::vec3::Vec3;
+
+use std::path::Path;
+use std::sync::Arc;
+
+// nearest-neighbour lookup, u runs left to right and v bottom to top
+#[derive(Clone, Debug)]
+pub struct ImageTexture {
+    image: Arc<image::RgbImage>,
+}
+
+impl ImageTexture {
+    pub fn new(image: image::RgbImage) -> ImageTexture {
+        ImageTexture {
+            image: Arc::new(image),
+        }
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<ImageTexture, Error> {
+        let image = image::open(path)?.to_rgb();
+        Ok(ImageTexture::new(image))
+    }
+}
+
+impl PartialEq for ImageTexture {
+    fn eq(&self, other: &ImageTexture) -> bool {
+        Arc::ptr_eq(&self.image, &other.image)
+            || (self.image.dimensions() == other.image.dimensions()
+                && **self.image == **other.image)
+    }
+}
+
+impl Texturable for ImageTexture {
+    fn value(&self, u: f64, v: f64, _p: &Vec3) -> Vec3 {
+        let (width, height) = self.image.dimensions();
+        if width == 0 || height == 0 {
+            return Vec3::new(0.0, 1.0, 1.0);
+        }
+
+        let u = u.clamp(0.0, 1.0);
+        let v = 1.0 - v.clamp(0.0, 1.0);
+        let i = u32::min((u * width as f64) as u32, width - 1);
+        let j = u32::min((v * height as f64) as u32, height - 1);
+
+        let pixel = self.image.get_pixel(i, j);
+        let scale = 1.0 / 255.0;
+        Vec3::new(
+            scale * f64::from(pixel[0]),
+            scale * f64::from(pixel[1]),
+            scale * f64::from(pixel[2]),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn lookup() {
+        let mut image = image::RgbImage::new(2, 2);
+        image.put_pixel(0, 0, image::Rgb([255, 0, 0]));
+        image.put_pixel(1, 1, image::Rgb([0, 0, 255]));
+        let texture = ImageTexture::new(image);
+        let p = Vec3::new(0.0, 0.0, 0.0);
+
+        // top left of the image is u = 0, v = 1
+        assert_eq!(texture.value(0.1, 0.9, &p), Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(texture.value(0.9, 0.1, &p), Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(texture.value(1.0, 0.0, &p), Vec3::new(0.0, 0.0, 1.0));
+    }
+}
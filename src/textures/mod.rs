@@ -0,0 +1,35 @@
+use crate::vec3::Vec3;
+
+pub mod checker;
+pub mod image_texture;
+pub mod noise;
+pub mod solid_color;
+
+pub trait Texturable {
+    fn value(&self, u: f64, v: f64, p: &Vec3) -> Vec3;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Texture {
+    SolidColor(solid_color::SolidColor),
+    Checker(checker::Checker),
+    Image(image_texture::ImageTexture),
+    Noise(noise::NoiseTexture),
+}
+
+impl Texture {
+    pub fn solid(color: Vec3) -> Texture {
+        Texture::SolidColor(solid_color::SolidColor::new(color))
+    }
+}
+
+impl Texturable for Texture {
+    fn value(&self, u: f64, v: f64, p: &Vec3) -> Vec3 {
+        match *self {
+            Texture::SolidColor(ref inner) => inner.value(u, v, p),
+            Texture::Checker(ref inner) => inner.value(u, v, p),
+            Texture::Image(ref inner) => inner.value(u, v, p),
+            Texture::Noise(ref inner) => inner.value(u, v, p),
+        }
+    }
+}
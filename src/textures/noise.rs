@@ -0,0 +1,182 @@
+use super::Texturable;
+use crate::vec3::Vec3;
+
+use rand::{Rng, SeedableRng};
+use std::sync::Arc;
+
+const POINT_COUNT: usize = 256;
+
+// perlin noise with random gradient vectors
+#[derive(Debug, PartialEq)]
+pub struct Perlin {
+    ranvec: Vec<Vec3>,
+    perm_x: Vec<usize>,
+    perm_y: Vec<usize>,
+    perm_z: Vec<usize>,
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Perlin {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+
+        let ranvec = (0..POINT_COUNT)
+            .map(|_| {
+                Vec3::unit_vector(Vec3::new(
+                    rng.gen_range(-1.0, 1.0),
+                    rng.gen_range(-1.0, 1.0),
+                    rng.gen_range(-1.0, 1.0),
+                ))
+            })
+            .collect();
+
+        Perlin {
+            ranvec,
+            perm_x: generate_perm(&mut rng),
+            perm_y: generate_perm(&mut rng),
+            perm_z: generate_perm(&mut rng),
+        }
+    }
+
+    // in [-1, 1]
+    pub fn noise(&self, p: &Vec3) -> f64 {
+        let u = p.x - p.x.floor();
+        let v = p.y - p.y.floor();
+        let w = p.z - p.z.floor();
+
+        let i = p.x.floor() as i64;
+        let j = p.y.floor() as i64;
+        let k = p.z.floor() as i64;
+
+        let mut c = [[[Vec3::new(0.0, 0.0, 0.0); 2]; 2]; 2];
+        for (di, plane) in c.iter_mut().enumerate() {
+            for (dj, row) in plane.iter_mut().enumerate() {
+                for (dk, corner) in row.iter_mut().enumerate() {
+                    *corner = self.ranvec[self.perm_x[wrap(i + di as i64)]
+                        ^ self.perm_y[wrap(j + dj as i64)]
+                        ^ self.perm_z[wrap(k + dk as i64)]];
+                }
+            }
+        }
+
+        perlin_interp(&c, u, v, w)
+    }
+
+    pub fn turbulence(&self, p: &Vec3, depth: u32) -> f64 {
+        let mut accum = 0.0;
+        let mut temp_p = *p;
+        let mut weight = 1.0;
+
+        for _ in 0..depth {
+            accum += weight * self.noise(&temp_p);
+            weight *= 0.5;
+            temp_p = temp_p * 2.0;
+        }
+
+        accum.abs()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NoiseStyle {
+    Noise,
+    Turbulence,
+    Marble,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NoiseTexture {
+    perlin: Arc<Perlin>,
+    scale: f64,
+    style: NoiseStyle,
+}
+
+impl NoiseTexture {
+    pub fn new(scale: f64, style: NoiseStyle) -> NoiseTexture {
+        NoiseTexture::with_seed(scale, style, 0)
+    }
+
+    pub fn with_seed(scale: f64, style: NoiseStyle, seed: u64) -> NoiseTexture {
+        NoiseTexture {
+            perlin: Arc::new(Perlin::new(seed)),
+            scale,
+            style,
+        }
+    }
+}
+
+impl Texturable for NoiseTexture {
+    fn value(&self, _u: f64, _v: f64, p: &Vec3) -> Vec3 {
+        let white = Vec3::new(1.0, 1.0, 1.0);
+        let scaled = self.scale * *p;
+
+        match self.style {
+            NoiseStyle::Noise => white * 0.5 * (1.0 + self.perlin.noise(&scaled)),
+            NoiseStyle::Turbulence => white * self.perlin.turbulence(&scaled, 7),
+            NoiseStyle::Marble => {
+                white * 0.5 * (1.0 + f64::sin(scaled.z + 10.0 * self.perlin.turbulence(p, 7)))
+            }
+        }
+    }
+}
+
+fn wrap(i: i64) -> usize {
+    (i & (POINT_COUNT as i64 - 1)) as usize
+}
+
+fn generate_perm<R: Rng>(rng: &mut R) -> Vec<usize> {
+    let mut p: Vec<usize> = (0..POINT_COUNT).collect();
+    for i in (1..POINT_COUNT).rev() {
+        let target = rng.gen_range(0, i + 1);
+        p.swap(i, target);
+    }
+    p
+}
+
+fn perlin_interp(c: &[[[Vec3; 2]; 2]; 2], u: f64, v: f64, w: f64) -> f64 {
+    // hermite smoothing
+    let uu = u * u * (3.0 - 2.0 * u);
+    let vv = v * v * (3.0 - 2.0 * v);
+    let ww = w * w * (3.0 - 2.0 * w);
+
+    let mut accum = 0.0;
+    for (i, plane) in c.iter().enumerate() {
+        for (j, row) in plane.iter().enumerate() {
+            for (k, corner) in row.iter().enumerate() {
+                let (fi, fj, fk) = (i as f64, j as f64, k as f64);
+                let weight = Vec3::new(u - fi, v - fj, w - fk);
+                accum += (fi * uu + (1.0 - fi) * (1.0 - uu))
+                    * (fj * vv + (1.0 - fj) * (1.0 - vv))
+                    * (fk * ww + (1.0 - fk) * (1.0 - ww))
+                    * corner.dot(&weight);
+            }
+        }
+    }
+
+    accum
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn noise_range() {
+        let perlin = Perlin::new(1);
+
+        for i in 0..1000 {
+            let t = i as f64 * 0.137;
+            let n = perlin.noise(&Vec3::new(t, 2.0 * t, -t));
+            assert!((-1.0..=1.0).contains(&n));
+        }
+
+        // zero at lattice points
+        assert_eq!(perlin.noise(&Vec3::new(3.0, -2.0, 5.0)), 0.0);
+    }
+
+    #[test]
+    fn seeded() {
+        let p = Vec3::new(0.3, 1.7, -4.2);
+
+        assert_eq!(Perlin::new(9).noise(&p), Perlin::new(9).noise(&p));
+    }
+}
@@ -0,0 +1,19 @@
+use super::Texturable;
+use crate::vec3::Vec3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SolidColor {
+    color: Vec3,
+}
+
+impl SolidColor {
+    pub fn new(color: Vec3) -> SolidColor {
+        SolidColor { color }
+    }
+}
+
+impl Texturable for SolidColor {
+    fn value(&self, _u: f64, _v: f64, _p: &Vec3) -> Vec3 {
+        self.color
+    }
+}
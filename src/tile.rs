@@ -0,0 +1,59 @@
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tile {
+    pub x0: u32,
+    pub y0: u32,
+    pub x1: u32,
+    pub y1: u32,
+}
+
+impl Tile {
+    pub fn new(x0: u32, y0: u32, x1: u32, y1: u32) -> Tile {
+        Tile { x0, y0, x1, y1 }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.x1 - self.x0
+    }
+
+    pub fn height(&self) -> u32 {
+        self.y1 - self.y0
+    }
+}
+
+// splits an nx * ny image into square tiles, row by row from the top left.
+// tiles on the right and bottom edges are clipped to the image.
+pub fn tiles(nx: u32, ny: u32, size: u32) -> Vec<Tile> {
+    let mut tiles = Vec::new();
+
+    let mut y0 = 0;
+    while y0 < ny {
+        let y1 = u32::min(y0 + size, ny);
+        let mut x0 = 0;
+        while x0 < nx {
+            let x1 = u32::min(x0 + size, nx);
+            tiles.push(Tile::new(x0, y0, x1, y1));
+            x0 = x1;
+        }
+        y0 = y1;
+    }
+
+    tiles
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn tiles_cover_image() {
+        let tiles = tiles(70, 33, 32);
+
+        assert_eq!(tiles.len(), 6);
+        assert_eq!(tiles[0], Tile::new(0, 0, 32, 32));
+        assert_eq!(tiles[2], Tile::new(64, 0, 70, 32));
+        assert_eq!(tiles[5], Tile::new(64, 32, 70, 33));
+
+        let area: u32 = tiles.iter().map(|t| t.width() * t.height()).sum();
+        assert_eq!(area, 70 * 33);
+    }
+}
@@ -0,0 +1,194 @@
+use crate::sampler::Sampler;
+use crate::vec3::Vec3;
+
+pub const NAMES: [&str; 5] = ["clamp", "reinhard", "reinhard_extended", "aces", "hable"];
+
+// white point of `ReinhardExtended` when none is given
+pub const DEFAULT_WHITE: f64 = 4.0;
+
+// maps linear radiance into [0, 1], channel by channel
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ToneMap {
+    Clamp,
+    Reinhard,
+    // reinhard that maps `white` and everything brighter to 1
+    ReinhardExtended { white: f64 },
+    // Narkowicz's fit of the ACES filmic curve
+    Aces,
+    // Hable's Uncharted 2 filmic curve
+    Hable,
+}
+
+impl ToneMap {
+    pub fn from_name(name: &str) -> Option<ToneMap> {
+        match name {
+            "clamp" => Some(ToneMap::Clamp),
+            "reinhard" => Some(ToneMap::Reinhard),
+            "reinhard_extended" => Some(ToneMap::ReinhardExtended {
+                white: DEFAULT_WHITE,
+            }),
+            "aces" => Some(ToneMap::Aces),
+            "hable" => Some(ToneMap::Hable),
+            _ => None,
+        }
+    }
+
+    pub fn map(&self, col: Vec3) -> Vec3 {
+        Vec3::new(
+            self.channel(col.r()),
+            self.channel(col.g()),
+            self.channel(col.b()),
+        )
+    }
+
+    fn channel(&self, x: f64) -> f64 {
+        // also catches NaN
+        let x = if x > 0.0 { x } else { 0.0 };
+
+        let mapped = match *self {
+            ToneMap::Clamp => x,
+            ToneMap::Reinhard => x / (1.0 + x),
+            ToneMap::ReinhardExtended { white } => x * (1.0 + x / (white * white)) / (1.0 + x),
+            ToneMap::Aces => (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14),
+            ToneMap::Hable => hable(2.0 * x) / hable(11.2),
+        };
+        mapped.min(1.0)
+    }
+}
+
+fn hable(x: f64) -> f64 {
+    let (a, b, c, d, e, f) = (0.15, 0.50, 0.10, 0.20, 0.02, 0.30);
+    (x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f) - e / f
+}
+
+// encodes tone mapped values for display
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Transfer {
+    Gamma(f64),
+    Srgb,
+}
+
+impl Transfer {
+    pub fn encode(&self, v: f64) -> f64 {
+        match *self {
+            Transfer::Gamma(gamma) => v.powf(1.0 / gamma),
+            Transfer::Srgb => {
+                if v <= 0.003_130_8 {
+                    12.92 * v
+                } else {
+                    1.055 * v.powf(1.0 / 2.4) - 0.055
+                }
+            }
+        }
+    }
+}
+
+// the display transform between the linear framebuffer and 8-bit images
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ToneMapping {
+    pub tone_map: ToneMap,
+    // in stops, the framebuffer is scaled by 2^exposure before tone mapping
+    pub exposure: f64,
+    pub transfer: Transfer,
+    // adds noise before quantizing, which breaks up banding in smooth gradients
+    pub dither: bool,
+}
+
+impl ToneMapping {
+    pub fn apply(&self, col: Vec3) -> Vec3 {
+        let mapped = self.tone_map.map(col * 2f64.powf(self.exposure));
+        Vec3::new(
+            self.transfer.encode(mapped.r()),
+            self.transfer.encode(mapped.g()),
+            self.transfer.encode(mapped.b()),
+        )
+    }
+
+    pub fn to_rgb8(&self, col: Vec3, x: u32, y: u32) -> [u8; 3] {
+        let col = self.apply(col);
+
+        if !self.dither {
+            return [
+                (255.99 * col.r()) as u8,
+                (255.99 * col.g()) as u8,
+                (255.99 * col.b()) as u8,
+            ];
+        }
+
+        // triangular noise of up to one step either way, the same for every render
+        let mut sampler = Sampler::for_sample(0, x, y, 0);
+        let mut quantize = |v: f64| {
+            let noise = sampler.next_f64() - sampler.next_f64();
+            (255.99 * v + noise).clamp(0.0, 255.0) as u8
+        };
+        [quantize(col.r()), quantize(col.g()), quantize(col.b())]
+    }
+}
+
+impl Default for ToneMapping {
+    fn default() -> ToneMapping {
+        ToneMapping {
+            tone_map: ToneMap::Clamp,
+            exposure: 0.0,
+            transfer: Transfer::Gamma(2.0),
+            dither: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn operators_stay_in_range() {
+        for name in NAMES.iter() {
+            let tone_map = ToneMap::from_name(name).unwrap();
+
+            assert_eq!(
+                tone_map.map(Vec3::new(0.0, -1.0, 0.0)),
+                Vec3::new(0.0, 0.0, 0.0)
+            );
+            let bright = tone_map.map(Vec3::new(1000.0, 1000.0, 1000.0));
+            assert!(
+                bright.r() <= 1.0 && bright.r() > 0.9,
+                "{} {:?}",
+                name,
+                bright
+            );
+        }
+
+        assert_eq!(ToneMap::Reinhard.map(Vec3::new(1.0, 3.0, 0.0)).g(), 0.75);
+        assert_eq!(ToneMap::ReinhardExtended { white: 2.0 }.channel(2.0), 1.0);
+    }
+
+    #[test]
+    fn srgb() {
+        assert_eq!(Transfer::Srgb.encode(0.0), 0.0);
+        assert!((Transfer::Srgb.encode(1.0) - 1.0).abs() < 1e-12);
+        assert!((Transfer::Srgb.encode(0.5) - 0.735_356_983).abs() < 1e-6);
+    }
+
+    #[test]
+    fn exposure_and_dither() {
+        let mut tone_mapping = ToneMapping::default();
+        assert_eq!(
+            tone_mapping.to_rgb8(Vec3::new(0.25, 1.0, 2.0), 0, 0),
+            [127, 255, 255]
+        );
+
+        tone_mapping.exposure = -1.0;
+        assert_eq!(
+            tone_mapping.to_rgb8(Vec3::new(0.5, 2.0, 4.0), 0, 0),
+            [127, 255, 255]
+        );
+
+        // dithering moves a value by at most one step and never lifts black
+        tone_mapping.dither = true;
+        for x in 0..16 {
+            let [r, g, b] = tone_mapping.to_rgb8(Vec3::new(0.5, 0.0, 4.0), x, 0);
+            assert!((126..=128).contains(&r));
+            assert!(g == 0 && b >= 254);
+        }
+    }
+}
@@ -0,0 +1,695 @@
+use crate::reflexible::aabb::Aabb;
+use crate::vec3::Vec3;
+
+use std::ops::Mul;
+
+// pivots smaller than this make a matrix singular
+const EPSILON: f64 = 1e-12;
+
+// row major, points are column vectors
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Matrix4 {
+    pub m: [[f64; 4]; 4],
+}
+
+impl Matrix4 {
+    pub fn new(m: [[f64; 4]; 4]) -> Matrix4 {
+        Matrix4 { m }
+    }
+
+    pub fn identity() -> Matrix4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            row[i] = 1.0;
+        }
+        Matrix4 { m }
+    }
+
+    pub fn transpose(&self) -> Matrix4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                *v = self.m[j][i];
+            }
+        }
+        Matrix4 { m }
+    }
+
+    // gauss-jordan with partial pivoting, None for singular matrices
+    pub fn inverse(&self) -> Option<Matrix4> {
+        let mut a = self.m;
+        let mut inv = Matrix4::identity().m;
+
+        for col in 0..4 {
+            let pivot = (col..4)
+                .max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))
+                .unwrap();
+            let magnitude = a[pivot][col].abs();
+            if magnitude.is_nan() || magnitude <= EPSILON {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+
+            let scale = 1.0 / a[col][col];
+            for j in 0..4 {
+                a[col][j] *= scale;
+                inv[col][j] *= scale;
+            }
+            for row in 0..4 {
+                if row != col {
+                    let factor = a[row][col];
+                    for j in 0..4 {
+                        a[row][j] -= factor * a[col][j];
+                        inv[row][j] -= factor * inv[col][j];
+                    }
+                }
+            }
+        }
+
+        Some(Matrix4 { m: inv })
+    }
+
+    pub fn transform_point(&self, p: Vec3) -> Vec3 {
+        let m = &self.m;
+        let x = m[0][0] * p.x + m[0][1] * p.y + m[0][2] * p.z + m[0][3];
+        let y = m[1][0] * p.x + m[1][1] * p.y + m[1][2] * p.z + m[1][3];
+        let z = m[2][0] * p.x + m[2][1] * p.y + m[2][2] * p.z + m[2][3];
+        let w = m[3][0] * p.x + m[3][1] * p.y + m[3][2] * p.z + m[3][3];
+
+        if w == 1.0 {
+            Vec3::new(x, y, z)
+        } else {
+            Vec3::new(x, y, z) / w
+        }
+    }
+
+    // ignores the translation
+    pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+        )
+    }
+}
+
+impl Mul for Matrix4 {
+    type Output = Matrix4;
+
+    fn mul(self, other: Matrix4) -> Matrix4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                *v = (0..4).map(|k| self.m[i][k] * other.m[k][j]).sum();
+            }
+        }
+        Matrix4 { m }
+    }
+}
+
+// a matrix together with its inverse, which is what moving rays into object space needs.
+// angles are in degrees like the camera's field of view.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    matrix: Matrix4,
+    inverse: Matrix4,
+}
+
+impl Transform {
+    pub fn identity() -> Transform {
+        Transform {
+            matrix: Matrix4::identity(),
+            inverse: Matrix4::identity(),
+        }
+    }
+
+    // None if `matrix` isn't affine (its last row must be 0, 0, 0, 1) or can't be inverted
+    pub fn from_matrix(matrix: Matrix4) -> Option<Transform> {
+        if matrix.m[3] != [0.0, 0.0, 0.0, 1.0] {
+            return None;
+        }
+        Some(Transform {
+            matrix,
+            inverse: matrix.inverse()?,
+        })
+    }
+
+    pub fn translate(offset: Vec3) -> Transform {
+        let matrix = |v: Vec3| {
+            Matrix4::new([
+                [1.0, 0.0, 0.0, v.x],
+                [0.0, 1.0, 0.0, v.y],
+                [0.0, 0.0, 1.0, v.z],
+                [0.0, 0.0, 0.0, 1.0],
+            ])
+        };
+
+        Transform {
+            matrix: matrix(offset),
+            inverse: matrix(-offset),
+        }
+    }
+
+    // None if any factor is zero
+    pub fn scale(factors: Vec3) -> Option<Transform> {
+        Transform::from_matrix(Matrix4::new([
+            [factors.x, 0.0, 0.0, 0.0],
+            [0.0, factors.y, 0.0, 0.0],
+            [0.0, 0.0, factors.z, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]))
+    }
+
+    pub fn rotate_x(degrees: f64) -> Transform {
+        Transform::rotate(Vec3::new(1.0, 0.0, 0.0), degrees)
+    }
+
+    pub fn rotate_y(degrees: f64) -> Transform {
+        Transform::rotate(Vec3::new(0.0, 1.0, 0.0), degrees)
+    }
+
+    pub fn rotate_z(degrees: f64) -> Transform {
+        Transform::rotate(Vec3::new(0.0, 0.0, 1.0), degrees)
+    }
+
+    // counterclockwise around `axis` when looking down it towards the origin
+    pub fn rotate(axis: Vec3, degrees: f64) -> Transform {
+        let a = Vec3::unit_vector(axis);
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let t = 1.0 - cos;
+
+        let matrix = Matrix4::new([
+            [
+                t * a.x * a.x + cos,
+                t * a.x * a.y - sin * a.z,
+                t * a.x * a.z + sin * a.y,
+                0.0,
+            ],
+            [
+                t * a.x * a.y + sin * a.z,
+                t * a.y * a.y + cos,
+                t * a.y * a.z - sin * a.x,
+                0.0,
+            ],
+            [
+                t * a.x * a.z - sin * a.y,
+                t * a.y * a.z + sin * a.x,
+                t * a.z * a.z + cos,
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+
+        // rotations are orthogonal
+        Transform {
+            matrix,
+            inverse: matrix.transpose(),
+        }
+    }
+
+    // this transform followed by `next`
+    pub fn then(&self, next: &Transform) -> Transform {
+        Transform {
+            matrix: next.matrix * self.matrix,
+            inverse: self.inverse * next.inverse,
+        }
+    }
+
+    pub fn inverted(&self) -> Transform {
+        Transform {
+            matrix: self.inverse,
+            inverse: self.matrix,
+        }
+    }
+
+    pub fn matrix(&self) -> &Matrix4 {
+        &self.matrix
+    }
+
+    pub fn inverse(&self) -> &Matrix4 {
+        &self.inverse
+    }
+
+    pub fn point(&self, p: Vec3) -> Vec3 {
+        self.matrix.transform_point(p)
+    }
+
+    pub fn vector(&self, v: Vec3) -> Vec3 {
+        self.matrix.transform_vector(v)
+    }
+
+    // normals go through the inverse transpose to stay perpendicular to scaled surfaces.
+    // the result isn't normalized.
+    pub fn normal(&self, n: Vec3) -> Vec3 {
+        self.inverse.transpose().transform_vector(n)
+    }
+
+    // rotations, translations, mirrors and uniform scales keep the angles between directions,
+    // and so the solid angles lights are sampled in
+    pub fn is_similarity(&self) -> bool {
+        let m = &self.matrix.m;
+        if m[3] != [0.0, 0.0, 0.0, 1.0] {
+            return false;
+        }
+        let [x, y, z] = [0, 1, 2].map(|j| Vec3::new(m[0][j], m[1][j], m[2][j]));
+        let scale = x.squared_length();
+        let close = |a: f64, b: f64| (a - b).abs() <= 1e-9 * scale;
+
+        close(y.squared_length(), scale)
+            && close(z.squared_length(), scale)
+            && close(x.dot(&y), 0.0)
+            && close(y.dot(&z), 0.0)
+            && close(z.dot(&x), 0.0)
+    }
+
+    // the box around the transformed corners of `bbox`
+    pub fn bounding_box(&self, bbox: &Aabb) -> Aabb {
+        let mut min = Vec3::new(f64::MAX, f64::MAX, f64::MAX);
+        let mut max = Vec3::new(f64::MIN, f64::MIN, f64::MIN);
+
+        for corner in 0..8 {
+            let pick = |bit: u32, lo: f64, hi: f64| if corner & bit == 0 { lo } else { hi };
+            let p = self.point(Vec3::new(
+                pick(1, bbox.min.x, bbox.max.x),
+                pick(2, bbox.min.y, bbox.max.y),
+                pick(4, bbox.min.z, bbox.max.z),
+            ));
+            min = Vec3::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z));
+            max = Vec3::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z));
+        }
+
+        Aabb::new(min, max)
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Transform {
+        Transform::identity()
+    }
+}
+
+// a transform going from `start` at `time0` to `end` at `time1`, held before and after.
+// translation, rotation and scale are interpolated separately so that turning objects keep
+// their shape on the way.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AnimatedTransform {
+    start: Transform,
+    end: Transform,
+    time0: f64,
+    time1: f64,
+    // None when nothing moves
+    parts: Option<[Parts; 2]>,
+}
+
+// an affine matrix split into translation * rotation * scale
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Parts {
+    translation: Vec3,
+    rotation: Quaternion,
+    scale: Matrix4,
+}
+
+impl AnimatedTransform {
+    pub fn new(start: Transform, end: Transform, time0: f64, time1: f64) -> AnimatedTransform {
+        let parts = if start != end && time0 < time1 {
+            Some([decompose(start.matrix()), decompose(end.matrix())])
+        } else {
+            None
+        };
+
+        AnimatedTransform {
+            start,
+            end,
+            time0,
+            time1,
+            parts,
+        }
+    }
+
+    pub fn fixed(transform: Transform) -> AnimatedTransform {
+        AnimatedTransform::new(transform, transform, 0.0, 0.0)
+    }
+
+    pub fn is_animated(&self) -> bool {
+        self.parts.is_some()
+    }
+
+    pub fn at(&self, time: f64) -> Transform {
+        let [p0, p1] = match self.parts {
+            Some(parts) if time > self.time0 => parts,
+            _ => return self.start,
+        };
+        if time >= self.time1 {
+            return self.end;
+        }
+
+        let t = (time - self.time0) / (self.time1 - self.time0);
+        let translation =
+            Transform::translate(p0.translation + t * (p1.translation - p0.translation));
+        let rotation = p0.rotation.slerp(&p1.rotation, t).to_matrix();
+        let mut scale = p0.scale;
+        for (i, row) in scale.m.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                *v += t * (p1.scale.m[i][j] - p0.scale.m[i][j]);
+            }
+        }
+
+        // the scales in between are invertible unless one end mirrors and the other doesn't
+        match scale.inverse() {
+            Some(inverse) => Transform {
+                matrix: scale,
+                inverse,
+            }
+            .then(&Transform {
+                matrix: rotation,
+                inverse: rotation.transpose(),
+            })
+            .then(&translation),
+            None => self.start,
+        }
+    }
+
+    // the box around `bbox` over the whole motion
+    pub fn bounding_box(&self, bbox: &Aabb) -> Aabb {
+        let parts = match self.parts {
+            Some(parts) => parts,
+            None => return self.start.bounding_box(bbox),
+        };
+
+        let mut result = self.start.bounding_box(bbox);
+        let mut radius: f64 = 0.0;
+        for step in 0..=BOUNDS_STEPS {
+            let time =
+                self.time0 + (self.time1 - self.time0) * f64::from(step) / f64::from(BOUNDS_STEPS);
+            let transform = self.at(time);
+            result = Aabb::surrounding(&result, &transform.bounding_box(bbox));
+
+            // how far the corners are from the point they turn around
+            let center = transform.point(Vec3::new(0.0, 0.0, 0.0));
+            for corner in corners(bbox).iter() {
+                radius = radius.max((transform.point(*corner) - center).length());
+            }
+        }
+
+        // corners move along arcs between the steps, which bulge out of the sampled boxes
+        let angle = parts[0].rotation.angle(&parts[1].rotation) / f64::from(BOUNDS_STEPS);
+        let bulge = radius * (1.0 - (angle / 2.0).cos());
+        let padding = Vec3::new(bulge, bulge, bulge);
+        Aabb::new(result.min - padding, result.max + padding)
+    }
+}
+
+// times sampled to bound a motion
+const BOUNDS_STEPS: u32 = 64;
+
+fn corners(bbox: &Aabb) -> [Vec3; 8] {
+    let mut corners = [bbox.min; 8];
+    for (i, corner) in corners.iter_mut().enumerate() {
+        let pick = |bit: usize, lo: f64, hi: f64| if i & bit == 0 { lo } else { hi };
+        *corner = Vec3::new(
+            pick(1, bbox.min.x, bbox.max.x),
+            pick(2, bbox.min.y, bbox.max.y),
+            pick(4, bbox.min.z, bbox.max.z),
+        );
+    }
+    corners
+}
+
+// polar decomposition, averaging the matrix with its inverse transpose until it's a rotation
+fn decompose(m: &Matrix4) -> Parts {
+    let translation = Vec3::new(m.m[0][3], m.m[1][3], m.m[2][3]);
+    let mut linear = *m;
+    for i in 0..3 {
+        linear.m[i][3] = 0.0;
+        linear.m[3][i] = 0.0;
+    }
+    linear.m[3][3] = 1.0;
+
+    let mut rotation = linear;
+    for _ in 0..100 {
+        let inverse_transpose = match rotation.inverse() {
+            Some(inverse) => inverse.transpose(),
+            None => break,
+        };
+        let mut next = rotation;
+        let mut change: f64 = 0.0;
+        for i in 0..3 {
+            for j in 0..3 {
+                next.m[i][j] = 0.5 * (rotation.m[i][j] + inverse_transpose.m[i][j]);
+                change = change.max((next.m[i][j] - rotation.m[i][j]).abs());
+            }
+        }
+        rotation = next;
+        if change < 1e-12 {
+            break;
+        }
+    }
+
+    // mirroring goes into the scale, quaternions only hold proper rotations
+    if determinant3(&rotation) < 0.0 {
+        for row in rotation.m.iter_mut().take(3) {
+            for v in row.iter_mut().take(3) {
+                *v = -*v;
+            }
+        }
+    }
+
+    Parts {
+        translation,
+        rotation: Quaternion::from_matrix(&rotation),
+        scale: rotation.transpose() * linear,
+    }
+}
+
+fn determinant3(m: &Matrix4) -> f64 {
+    let m = &m.m;
+    m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+}
+
+// unit quaternion for interpolating rotations
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Quaternion {
+    x: f64,
+    y: f64,
+    z: f64,
+    w: f64,
+}
+
+impl Quaternion {
+    fn from_matrix(m: &Matrix4) -> Quaternion {
+        let m = &m.m;
+        let trace = m[0][0] + m[1][1] + m[2][2];
+
+        if trace > 0.0 {
+            let s = (trace + 1.0).sqrt();
+            let k = 0.5 / s;
+            return Quaternion {
+                x: (m[2][1] - m[1][2]) * k,
+                y: (m[0][2] - m[2][0]) * k,
+                z: (m[1][0] - m[0][1]) * k,
+                w: 0.5 * s,
+            };
+        }
+
+        // the largest diagonal element keeps the square root away from zero
+        let i = if m[1][1] > m[0][0] { 1 } else { 0 };
+        let i = if m[2][2] > m[i][i] { 2 } else { i };
+        let (j, k) = ((i + 1) % 3, (i + 2) % 3);
+        let s = (m[i][i] - m[j][j] - m[k][k] + 1.0).sqrt();
+        let f = 0.5 / s;
+
+        let mut v = [0.0; 3];
+        v[i] = 0.5 * s;
+        v[j] = (m[j][i] + m[i][j]) * f;
+        v[k] = (m[k][i] + m[i][k]) * f;
+        Quaternion {
+            x: v[0],
+            y: v[1],
+            z: v[2],
+            w: (m[k][j] - m[j][k]) * f,
+        }
+    }
+
+    fn to_matrix(self) -> Matrix4 {
+        let Quaternion { x, y, z, w } = self;
+
+        Matrix4::new([
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - z * w),
+                2.0 * (x * z + y * w),
+                0.0,
+            ],
+            [
+                2.0 * (x * y + z * w),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - x * w),
+                0.0,
+            ],
+            [
+                2.0 * (x * z - y * w),
+                2.0 * (y * z + x * w),
+                1.0 - 2.0 * (x * x + y * y),
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    fn dot(&self, other: &Quaternion) -> f64 {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+
+    // the rotation between the two, in radians
+    fn angle(&self, other: &Quaternion) -> f64 {
+        2.0 * self.dot(other).abs().min(1.0).acos()
+    }
+
+    // along the shorter arc
+    fn slerp(&self, other: &Quaternion, t: f64) -> Quaternion {
+        let mut cos = self.dot(other);
+        let sign = if cos < 0.0 { -1.0 } else { 1.0 };
+        cos *= sign;
+
+        let (a, b) = if cos > 0.9995 {
+            // nearly parallel, a normalized lerp is as good and avoids dividing by ~0
+            (1.0 - t, t)
+        } else {
+            let theta = cos.acos();
+            let sin = theta.sin();
+            (((1.0 - t) * theta).sin() / sin, (t * theta).sin() / sin)
+        };
+        let b = b * sign;
+
+        let q = Quaternion {
+            x: a * self.x + b * other.x,
+            y: a * self.y + b * other.y,
+            z: a * self.z + b * other.z,
+            w: a * self.w + b * other.w,
+        };
+        let length = q.dot(&q).sqrt();
+        Quaternion {
+            x: q.x / length,
+            y: q.y / length,
+            z: q.z / length,
+            w: q.w / length,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn close(a: Vec3, b: Vec3) -> bool {
+        (a - b).length() < 1e-9
+    }
+
+    #[test]
+    fn compose() {
+        let t = Transform::scale(Vec3::new(2.0, 2.0, 2.0))
+            .unwrap()
+            .then(&Transform::rotate_y(90.0))
+            .then(&Transform::translate(Vec3::new(1.0, 0.0, 0.0)));
+
+        // x goes to -z when turning counterclockwise around y
+        let p = t.point(Vec3::new(1.0, 0.0, 0.0));
+        assert!(close(p, Vec3::new(1.0, 0.0, -2.0)));
+        assert!(close(t.inverted().point(p), Vec3::new(1.0, 0.0, 0.0)));
+        assert!(close(
+            t.vector(Vec3::new(0.0, 1.0, 0.0)),
+            Vec3::new(0.0, 2.0, 0.0)
+        ));
+    }
+
+    #[test]
+    fn inverse() {
+        let m = Matrix4::new([
+            [2.0, 0.0, 1.0, 3.0],
+            [0.0, 1.0, 0.0, -1.0],
+            [1.0, 0.0, 1.0, 0.5],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let product = m * m.inverse().unwrap();
+        for i in 0..4 {
+            for j in 0..4 {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((product.m[i][j] - expected).abs() < 1e-12);
+            }
+        }
+
+        assert!(Transform::scale(Vec3::new(1.0, 0.0, 1.0)).is_none());
+        // projective matrices are invertible, but not transforms
+        let mut projective = m;
+        projective.m[3] = [0.0, 0.0, 1.0, 0.0];
+        assert!(projective.inverse().is_some());
+        assert!(Transform::from_matrix(projective).is_none());
+    }
+
+    #[test]
+    fn similarity() {
+        let t = Transform::scale(Vec3::new(2.0, 2.0, 2.0))
+            .unwrap()
+            .then(&Transform::rotate(Vec3::new(1.0, 2.0, 3.0), 40.0))
+            .then(&Transform::translate(Vec3::new(1.0, -2.0, 0.5)));
+        assert!(t.is_similarity());
+        assert!(Transform::scale(Vec3::new(-1.0, 1.0, 1.0))
+            .unwrap()
+            .is_similarity());
+        assert!(!Transform::scale(Vec3::new(1.0, 0.5, 1.0))
+            .unwrap()
+            .is_similarity());
+    }
+
+    #[test]
+    fn normals() {
+        // squashing a 45 degree slope makes it steeper, its normal flatter
+        let t = Transform::scale(Vec3::new(1.0, 0.5, 1.0)).unwrap();
+        let n = Vec3::unit_vector(t.normal(Vec3::new(-1.0, 1.0, 0.0)));
+        let slope = t.vector(Vec3::new(1.0, 1.0, 0.0));
+
+        assert!(n.dot(&slope).abs() < 1e-12);
+    }
+
+    #[test]
+    fn animated() {
+        let start = Transform::translate(Vec3::new(1.0, 0.0, 0.0));
+        let end = Transform::scale(Vec3::new(2.0, 2.0, 2.0))
+            .unwrap()
+            .then(&Transform::rotate_y(90.0))
+            .then(&Transform::translate(Vec3::new(1.0, 4.0, 0.0)));
+        let motion = AnimatedTransform::new(start, end, 0.0, 1.0);
+
+        assert_eq!(motion.at(-1.0), start);
+        assert_eq!(motion.at(1.0), end);
+
+        // halfway it's turned 45 degrees, scaled by 1.5 and moved up by 2
+        let expected = Transform::scale(Vec3::new(1.5, 1.5, 1.5))
+            .unwrap()
+            .then(&Transform::rotate_y(45.0))
+            .then(&Transform::translate(Vec3::new(1.0, 2.0, 0.0)));
+        let p = Vec3::new(0.3, -0.2, 0.7);
+        assert!(close(motion.at(0.5).point(p), expected.point(p)));
+        assert!(close(motion.at(0.5).inverted().point(expected.point(p)), p));
+
+        // every corner stays inside the bounds all the way, and they aren't much bigger
+        let bbox = Aabb::new(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0));
+        let bounds = motion.bounding_box(&bbox);
+        let mut max_x = f64::MIN;
+        for step in 0..=1000 {
+            let transform = motion.at(f64::from(step) / 1000.0);
+            for corner in corners(&bbox).iter() {
+                let p = transform.point(*corner);
+                assert!(p.x <= bounds.max.x && p.y <= bounds.max.y && p.z <= bounds.max.z);
+                assert!(p.x >= bounds.min.x && p.y >= bounds.min.y && p.z >= bounds.min.z);
+                max_x = max_x.max(p.x);
+            }
+        }
+        assert!(bounds.max.x - max_x < 0.01);
+        assert!(!AnimatedTransform::fixed(end).is_animated());
+    }
+}
@@ -45,6 +45,21 @@ impl Vec3 {
             z: v1.x * v2.y - v1.y * v2.x,
         }
     }
+
+    // a right handed orthonormal basis (u, v, w) with w along the given direction
+    pub fn basis(w: Vec3) -> (Vec3, Vec3, Vec3) {
+        let w = Vec3::unit_vector(w);
+        // any direction that isn't parallel to w
+        let helper = if w.z.abs() < 0.9 {
+            Vec3::new(0.0, 0.0, 1.0)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let u = Vec3::unit_vector(Vec3::cross(w, helper));
+        let v = Vec3::cross(w, u);
+
+        (u, v, w)
+    }
 }
 
 impl Add for Vec3 {
@@ -237,4 +252,23 @@ mod test {
         let neg = -vec1;
         assert_eq!(neg, Vec3::new(0.0, -2.0, -4.0));
     }
+
+    #[test]
+    fn basis() {
+        for w in &[
+            Vec3::new(0.0, 0.0, 2.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(-3.0, 1.0, 2.0),
+        ] {
+            let (u, v, n) = Vec3::basis(*w);
+            for (a, b) in &[(u, v), (v, n), (n, u)] {
+                assert!(a.dot(b).abs() < 1e-12);
+            }
+            for a in &[u, v, n] {
+                assert!((a.length() - 1.0).abs() < 1e-12);
+            }
+            assert!((Vec3::cross(u, v) - n).length() < 1e-12);
+            assert!((n - Vec3::unit_vector(*w)).length() < 1e-12);
+        }
+    }
 }